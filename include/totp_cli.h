@@ -0,0 +1,74 @@
+#ifndef TOTP_CLI_H
+#define TOTP_CLI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* error codes returned by the library, mapped from ErrorKind */
+#define TOTP_OK 0
+#define TOTP_ERR_INVALID_EXTENSION 1
+#define TOTP_ERR_INVALID_ARGUMENT 2
+#define TOTP_ERR_IO 3
+#define TOTP_ERR_JSON 4
+#define TOTP_ERR_YAML 5
+#define TOTP_ERR_MAC 6
+#define TOTP_ERR_URL 7
+#define TOTP_ERR_CHACHA 8
+#define TOTP_ERR_RAND 9
+#define TOTP_ERR_INTEGRITY 10
+#define TOTP_ERR_NULL_POINTER 11
+#define TOTP_ERR_NOT_FOUND 12
+#define TOTP_ERR_INVALID_UTF8 13
+/* an unexpected internal error, the library did not complete the call */
+#define TOTP_ERR_PANIC 14
+
+/* an opened totp file */
+typedef struct TotpVault TotpVault;
+
+/* returns a static description of the given error code */
+const char *totp_error_string(int code);
+
+/*
+ * opens a totp file with the given passphrase. the passphrase is only used
+ * if the file is encrypted. the vault must be freed with totp_vault_free.
+ *
+ * the cli uses the passphrase exactly as read from the terminal including
+ * the trailing newline, so files created by the cli need the newline
+ * included in the passphrase
+ */
+int totp_vault_open(const char *path, const char *passphrase, TotpVault **out);
+
+/* frees a vault returned by totp_vault_open */
+void totp_vault_free(TotpVault *vault);
+
+/* returns the number of records in the vault */
+size_t totp_vault_len(const TotpVault *vault);
+
+/*
+ * returns the name of the record at the given index in sorted order. the
+ * string is owned by the vault and is valid until the vault is freed.
+ * returns NULL if the index is out of range
+ */
+const char *totp_vault_name(const TotpVault *vault, size_t index);
+
+/*
+ * generates the current code of a record. the code must be freed with
+ * totp_string_free
+ */
+int totp_vault_code(const TotpVault *vault, const char *name, char **out);
+
+/* generates the code of a record for the given unix epoch */
+int totp_vault_code_at(const TotpVault *vault, const char *name, uint64_t time, char **out);
+
+/* frees a string returned by the library */
+void totp_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,560 @@
+use std::path::{Path, PathBuf};
+
+use crate::chacha;
+use crate::error::{Error, ErrorKind, Result};
+
+/// default amount of seconds that a key is held by the agent
+pub const DEFAULT_TIMEOUT: u64 = 900;
+
+/// a file currently unlocked in the agent with the seconds remaining
+pub struct UnlockedFile {
+    pub path: PathBuf,
+    pub remaining: u64,
+}
+
+/// resolves the path of a file to the value used by the agent
+///
+/// the same file can be referenced by many different paths so the path is
+/// canonicalized before being sent to the agent
+fn agent_path<P>(path: P) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let canonical = std::fs::canonicalize(path)?;
+
+    if canonical.to_string_lossy().contains('\n') {
+        return Err(Error::new(ErrorKind::InvalidArgument)
+            .with_message("file paths containing new lines are not supported by the agent"));
+    }
+
+    Ok(canonical)
+}
+
+/// attempts to retrieve the key of a file from the agent
+///
+/// any failure to communicate with the agent is treated as the file not
+/// being unlocked so that the caller can fall back to prompting the user
+pub fn get_key<P>(path: P) -> Option<chacha::Key>
+where
+    P: AsRef<Path>,
+{
+    let path = agent_path(path).ok()?;
+
+    imp::get_key(&path).ok().flatten()
+}
+
+/// stores the key of a file in the agent for the given amount of seconds
+///
+/// the agent will be started if it is not already running
+pub fn add_key<P>(path: P, key: &chacha::Key, timeout: u64) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = agent_path(path)?;
+
+    imp::add_key(&path, key, timeout)
+}
+
+/// removes the key of a file from the agent
+///
+/// returns false if the agent was not running or did not have the key
+pub fn remove_key<P>(path: P) -> Result<bool>
+where
+    P: AsRef<Path>,
+{
+    let path = agent_path(path)?;
+
+    imp::remove_key(&path)
+}
+
+/// removes all keys from the agent, stopping it
+///
+/// returns false if the agent was not running
+pub fn remove_all() -> Result<bool> {
+    imp::remove_all()
+}
+
+/// retrieves the files currently unlocked in the agent
+///
+/// returns None if the agent is not running
+pub fn status() -> Result<Option<Vec<UnlockedFile>>> {
+    imp::status()
+}
+
+/// runs the agent in the current process
+///
+/// will continue to run until all keys have expired or have been removed
+pub fn serve() -> Result<()> {
+    imp::serve()
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::os::unix::process::CommandExt;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    use zeroize::Zeroizing;
+
+    use super::UnlockedFile;
+    use crate::chacha;
+    use crate::error::{Error, ErrorKind, Result};
+
+    /// how long the agent will sleep between checking for new connections
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+    /// how long the agent will wait for its first key before exiting
+    const STARTUP_GRACE: Duration = Duration::from_secs(10);
+    /// how long a client will wait for a newly spawned agent
+    const SPAWN_WAIT: Duration = Duration::from_secs(2);
+    /// how long either side will wait on a stalled connection
+    const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// an unlocked file held by the agent
+    ///
+    /// the key is locked into memory and zeroed once the entry is dropped
+    struct Entry {
+        key: chacha::Key,
+        expires: Instant,
+    }
+
+    /// the directory that will contain the agent socket
+    ///
+    /// prefers XDG_RUNTIME_DIR and falls back to a user specific directory
+    /// in the temp directory
+    fn socket_dir() -> PathBuf {
+        if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+            let mut rtn = PathBuf::from(runtime);
+            rtn.push("totp-cli");
+            rtn
+        } else {
+            let uid = unsafe { libc::getuid() };
+            let mut rtn = std::env::temp_dir();
+            rtn.push(format!("totp-cli-{}", uid));
+            rtn
+        }
+    }
+
+    /// retrieves the socket path and ensures the containing directory is only
+    /// accessible by the current user
+    fn socket_path() -> Result<PathBuf> {
+        let dir = socket_dir();
+
+        if let Err(err) = std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+        {
+            return Err(Error::new(ErrorKind::IoError)
+                .with_message("failed to create agent directory")
+                .with_error(err));
+        }
+
+        let meta = std::fs::symlink_metadata(&dir)?;
+        let uid = unsafe { libc::getuid() };
+
+        if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+            return Err(Error::new(ErrorKind::IoError)
+                .with_message("agent directory is not private to the current user"));
+        }
+
+        Ok(dir.join("agent.sock"))
+    }
+
+    /// connects to the agent if it is running
+    fn connect() -> Result<Option<UnixStream>> {
+        let path = socket_path()?;
+
+        match UnixStream::connect(&path) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+                Ok(Some(stream))
+            }
+            Err(_err) => Ok(None),
+        }
+    }
+
+    /// starts a new agent process and waits for it to accept connections
+    fn spawn() -> Result<UnixStream> {
+        let exe = std::env::current_exe()?;
+
+        std::process::Command::new(exe)
+            .arg("agent")
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0)
+            .spawn()?;
+
+        let start = Instant::now();
+
+        while start.elapsed() < SPAWN_WAIT {
+            if let Some(stream) = connect()? {
+                return Ok(stream);
+            }
+
+            std::thread::sleep(POLL_INTERVAL / 4);
+        }
+
+        Err(Error::new(ErrorKind::IoError).with_message("failed to start the agent"))
+    }
+
+    /// sends a single request to the agent and returns the response lines
+    fn request(mut stream: UnixStream, msg: &str) -> Result<Vec<String>> {
+        stream.write_all(msg.as_bytes())?;
+        stream.write_all(b"\n")?;
+        stream.flush()?;
+
+        let mut rtn = Vec::new();
+
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+
+            if line == "end" {
+                return Ok(rtn);
+            }
+
+            rtn.push(line);
+        }
+
+        Err(Error::new(ErrorKind::IoError).with_message("agent closed connection unexpectedly"))
+    }
+
+    pub fn get_key(path: &Path) -> Result<Option<chacha::Key>> {
+        let Some(stream) = connect()? else {
+            return Ok(None);
+        };
+
+        let response = Zeroizing::new(request(stream, &format!("get {}", path.display()))?);
+
+        let Some(encoded) = response.first().and_then(|line| line.strip_prefix("key ")) else {
+            return Ok(None);
+        };
+
+        let Ok(decoded) = data_encoding::HEXLOWER.decode(encoded.as_bytes()) else {
+            return Err(Error::new(ErrorKind::IoError).with_message("agent returned invalid key"));
+        };
+        let decoded = Zeroizing::new(decoded);
+
+        let Some(key) = chacha::Key::from_slice(&decoded) else {
+            return Err(Error::new(ErrorKind::IoError).with_message("agent returned invalid key"));
+        };
+
+        Ok(Some(key))
+    }
+
+    pub fn add_key(path: &Path, key: &chacha::Key, timeout: u64) -> Result<()> {
+        let stream = if let Some(s) = connect()? {
+            s
+        } else {
+            spawn()?
+        };
+
+        let msg = Zeroizing::new(format!(
+            "set {} {} {}",
+            timeout,
+            data_encoding::HEXLOWER.encode(key.as_bytes()),
+            path.display()
+        ));
+        let response = request(stream, &msg)?;
+
+        if response.first().map(|line| line == "ok").unwrap_or(false) {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::IoError).with_message("agent failed to store key"))
+        }
+    }
+
+    pub fn remove_key(path: &Path) -> Result<bool> {
+        let Some(stream) = connect()? else {
+            return Ok(false);
+        };
+
+        let response = request(stream, &format!("lock {}", path.display()))?;
+
+        Ok(response.first().map(|line| line == "ok").unwrap_or(false))
+    }
+
+    pub fn remove_all() -> Result<bool> {
+        let Some(stream) = connect()? else {
+            return Ok(false);
+        };
+
+        request(stream, "lock-all")?;
+
+        Ok(true)
+    }
+
+    pub fn status() -> Result<Option<Vec<UnlockedFile>>> {
+        let Some(stream) = connect()? else {
+            return Ok(None);
+        };
+
+        let mut rtn = Vec::new();
+
+        for line in request(stream, "status")? {
+            let Some((remaining, path)) = line
+                .strip_prefix("file ")
+                .and_then(|rest| rest.split_once(' '))
+            else {
+                continue;
+            };
+
+            let Ok(remaining) = remaining.parse() else {
+                continue;
+            };
+
+            rtn.push(UnlockedFile {
+                path: PathBuf::from(path),
+                remaining,
+            });
+        }
+
+        Ok(Some(rtn))
+    }
+
+    /// processes a single request line and writes the response
+    fn handle_request<W>(
+        line: &str,
+        keys: &mut HashMap<PathBuf, Entry>,
+        stream: &mut W,
+    ) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        let (cmd, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match cmd {
+            "get" => {
+                if let Some(entry) = keys.get(Path::new(rest)) {
+                    let encoded =
+                        Zeroizing::new(data_encoding::HEXLOWER.encode(entry.key.as_bytes()));
+
+                    writeln!(stream, "key {}", encoded.as_str())?;
+                }
+            }
+            "set" => {
+                let mut split = rest.splitn(3, ' ');
+                let timeout = split.next().and_then(|v| v.parse::<u64>().ok());
+                let key = split
+                    .next()
+                    .and_then(|v| data_encoding::HEXLOWER.decode(v.as_bytes()).ok())
+                    .map(Zeroizing::new)
+                    .and_then(|v| chacha::Key::from_slice(&v));
+                let path = split.next();
+
+                if let (Some(timeout), Some(key), Some(path)) = (timeout, key, path) {
+                    keys.insert(
+                        PathBuf::from(path),
+                        Entry {
+                            key,
+                            expires: Instant::now() + Duration::from_secs(timeout),
+                        },
+                    );
+
+                    writeln!(stream, "ok")?;
+                }
+            }
+            "lock" => {
+                let removed = keys.remove(Path::new(rest));
+
+                if removed.is_some() {
+                    writeln!(stream, "ok")?;
+                }
+            }
+            "lock-all" => {
+                keys.clear();
+
+                writeln!(stream, "ok")?;
+            }
+            "status" => {
+                let now = Instant::now();
+
+                for (path, entry) in keys.iter() {
+                    writeln!(
+                        stream,
+                        "file {} {}",
+                        entry.expires.saturating_duration_since(now).as_secs(),
+                        path.display()
+                    )?;
+                }
+            }
+            _ => {}
+        }
+
+        writeln!(stream, "end")
+    }
+
+    /// reads a single request from a connection and responds to it
+    fn handle_stream(mut stream: UnixStream, keys: &mut HashMap<PathBuf, Entry>) -> Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        let mut line = Zeroizing::new(String::new());
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        handle_request(line.trim_end_matches('\n'), keys, &mut stream)?;
+
+        Ok(())
+    }
+
+    pub fn serve() -> Result<()> {
+        let path = socket_path()?;
+
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(Error::new(ErrorKind::IoError).with_message("agent is already running"));
+            }
+
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        listener.set_nonblocking(true)?;
+
+        let started = Instant::now();
+        let mut keys: HashMap<PathBuf, Entry> = HashMap::new();
+        let mut received = false;
+
+        loop {
+            match listener.accept() {
+                Ok((stream, _addr)) => {
+                    // a misbehaving client should not bring down the agent
+                    let _ = handle_stream(stream, &mut keys);
+
+                    received = received || !keys.is_empty();
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Err(err) => {
+                    let _ = std::fs::remove_file(&path);
+
+                    return Err(err.into());
+                }
+            }
+
+            let now = Instant::now();
+            keys.retain(|_, entry| entry.expires > now);
+
+            if keys.is_empty() && (received || started.elapsed() > STARTUP_GRACE) {
+                break;
+            }
+        }
+
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn respond(line: &str, keys: &mut HashMap<PathBuf, Entry>) -> String {
+            let mut out = Vec::new();
+
+            handle_request(line, keys, &mut out).unwrap();
+
+            String::from_utf8(out).unwrap()
+        }
+
+        #[test]
+        fn set_get_lock() {
+            let mut keys = HashMap::new();
+            let key = chacha::make_key("secret").unwrap();
+            let encoded = data_encoding::HEXLOWER.encode(key.as_bytes());
+
+            assert_eq!(respond("get /tmp/a b.totp", &mut keys), "end\n");
+            assert_eq!(
+                respond(&format!("set 60 {} /tmp/a b.totp", encoded), &mut keys),
+                "ok\nend\n"
+            );
+            assert_eq!(
+                respond("get /tmp/a b.totp", &mut keys),
+                format!("key {}\nend\n", encoded)
+            );
+            assert!(respond("status", &mut keys).starts_with("file "));
+            assert!(respond("status", &mut keys).ends_with(" /tmp/a b.totp\nend\n"));
+            assert_eq!(respond("lock /tmp/a b.totp", &mut keys), "ok\nend\n");
+            assert_eq!(respond("lock /tmp/a b.totp", &mut keys), "end\n");
+            assert_eq!(respond("get /tmp/a b.totp", &mut keys), "end\n");
+        }
+
+        #[test]
+        fn invalid_requests() {
+            let mut keys = HashMap::new();
+
+            for line in [
+                "",
+                "unknown",
+                "set",
+                "set 60",
+                "set abc 00 /tmp/a.totp",
+                "set 60 zz /tmp/a.totp",
+                "set 60 0011 /tmp/a.totp",
+            ] {
+                assert_eq!(respond(line, &mut keys), "end\n", "{:?}", line);
+            }
+
+            assert!(keys.is_empty());
+        }
+
+        #[test]
+        fn lock_all() {
+            let mut keys = HashMap::new();
+            let key = chacha::make_key("secret").unwrap();
+            let encoded = data_encoding::HEXLOWER.encode(key.as_bytes());
+
+            respond(&format!("set 60 {} /tmp/a.totp", encoded), &mut keys);
+            respond(&format!("set 60 {} /tmp/b.totp", encoded), &mut keys);
+
+            assert_eq!(respond("lock-all", &mut keys), "ok\nend\n");
+            assert!(keys.is_empty());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::path::Path;
+
+    use super::UnlockedFile;
+    use crate::chacha;
+    use crate::error::{Error, ErrorKind, Result};
+
+    fn unsupported() -> Error {
+        Error::new(ErrorKind::IoError).with_message("the agent is not supported on this platform")
+    }
+
+    pub fn get_key(_path: &Path) -> Result<Option<chacha::Key>> {
+        Ok(None)
+    }
+
+    pub fn add_key(_path: &Path, _key: &chacha::Key, _timeout: u64) -> Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn remove_key(_path: &Path) -> Result<bool> {
+        Ok(false)
+    }
+
+    pub fn remove_all() -> Result<bool> {
+        Ok(false)
+    }
+
+    pub fn status() -> Result<Option<Vec<UnlockedFile>>> {
+        Ok(None)
+    }
+
+    pub fn serve() -> Result<()> {
+        Err(unsupported())
+    }
+}
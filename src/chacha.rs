@@ -1,17 +1,79 @@
-use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305};
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    KeyInit, XChaCha20Poly1305,
+};
 use hkdf::Hkdf;
-use rand::TryRngCore;
+use zeroize::Zeroize;
 
 use crate::error::{Error, ErrorKind, Result};
+use crate::platform::{Random, System};
+use crate::secret;
 
 /// key length required for chacha encryption
 pub const KEY_LEN: usize = 32;
 /// nonce length required for chacha encryption
 pub const NONCE_LEN: usize = 24;
+/// length of the authentication tag appended to encrypted data
+pub const TAG_LEN: usize = 16;
 
-pub type Key = [u8; KEY_LEN];
 pub type Nonce = [u8; NONCE_LEN];
 
+/// key used for chacha encryption
+///
+/// the bytes are stored on the heap so that they have a stable address that
+/// can be locked into memory, preventing them from being written to swap.
+/// the key is zeroed when dropped but the memory is left locked since the
+/// page may hold other keys. the Debug impl will not print it
+pub struct Key(Box<[u8; KEY_LEN]>);
+
+impl Key {
+    /// creates a zeroed key that has been locked into memory
+    fn zeroed() -> Self {
+        let bytes = Box::new([0u8; KEY_LEN]);
+
+        secret::lock_memory(bytes.as_ptr(), bytes.len());
+
+        Key(bytes)
+    }
+
+    /// creates a key from the given slice if it is the correct length
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        if slice.len() != KEY_LEN {
+            return None;
+        }
+
+        let mut rtn = Self::zeroed();
+        rtn.0.copy_from_slice(slice);
+
+        Some(rtn)
+    }
+
+    /// returns the bytes of the key
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+}
+
+impl Clone for Key {
+    fn clone(&self) -> Self {
+        let mut rtn = Self::zeroed();
+        rtn.0.copy_from_slice(self.0.as_slice());
+        rtn
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Key([REDACTED])")
+    }
+}
+
 /// created a valid key from the variable length secret
 ///
 /// used HKDF with SHA3_256 to create a valid length key for use in chacha
@@ -21,10 +83,10 @@ where
     S: AsRef<[u8]>,
 {
     let kdf: Hkdf<sha3::Sha3_256> = Hkdf::new(None, secret.as_ref());
-    let mut output = [0u8; KEY_LEN];
+    let mut output = Key::zeroed();
     let info: [u8; 0] = [];
 
-    if let Err(_err) = kdf.expand(&info, &mut output) {
+    if let Err(_err) = kdf.expand(&info, output.0.as_mut_slice()) {
         return Err(
             Error::new(ErrorKind::ChaChaError).with_message("failed to create a valid key length")
         );
@@ -35,15 +97,23 @@ where
 
 /// creates a random nonce of given size for chacha encryption
 ///
-/// uses OsRng to fill the nonce array
-pub fn make_nonce() -> Result<Nonce> {
+/// uses the given random source to fill the nonce array
+pub fn make_nonce_with<R>(rng: &mut R) -> Result<Nonce>
+where
+    R: Random + ?Sized,
+{
     let mut nonce = [0u8; NONCE_LEN];
 
-    rand::rngs::OsRng.try_fill_bytes(&mut nonce)?;
+    rng.fill(&mut nonce)?;
 
     Ok(nonce)
 }
 
+/// creates a random nonce using the random source of the system
+pub fn make_nonce() -> Result<Nonce> {
+    make_nonce_with(&mut System)
+}
+
 /// decrypts data using chacha
 ///
 /// with the provided key and nonce, the data given will attempt to be
@@ -53,7 +123,19 @@ pub fn decrypt_data<D>(key: &Key, nonce: &Nonce, data: D) -> Result<Vec<u8>>
 where
     D: AsRef<[u8]>,
 {
-    let cipher = match XChaCha20Poly1305::new_from_slice(key) {
+    decrypt_data_aad(key, nonce, data, [])
+}
+
+/// decrypts data using chacha with additional authenticated data
+///
+/// the aad is not part of the encrypted data but must be the same as the
+/// aad given when encrypting or decryption will fail
+pub fn decrypt_data_aad<D, A>(key: &Key, nonce: &Nonce, data: D, aad: A) -> Result<Vec<u8>>
+where
+    D: AsRef<[u8]>,
+    A: AsRef<[u8]>,
+{
+    let cipher = match XChaCha20Poly1305::new_from_slice(key.as_bytes()) {
         Ok(c) => c,
         Err(err) => {
             return Err(Error::new(ErrorKind::ChaChaError)
@@ -62,7 +144,12 @@ where
         }
     };
 
-    cipher.decrypt(nonce.into(), data.as_ref()).map_err(|err| {
+    let payload = Payload {
+        msg: data.as_ref(),
+        aad: aad.as_ref(),
+    };
+
+    cipher.decrypt(nonce.into(), payload).map_err(|err| {
         Error::new(ErrorKind::ChaChaError)
             .with_message("failed to decrypt requested data")
             .with_error(err)
@@ -77,7 +164,19 @@ pub fn encrypt_data<D>(key: &Key, nonce: &Nonce, data: D) -> Result<Vec<u8>>
 where
     D: AsRef<[u8]>,
 {
-    let cipher = match XChaCha20Poly1305::new_from_slice(key) {
+    encrypt_data_aad(key, nonce, data, [])
+}
+
+/// encrypts data using chacha with additional authenticated data
+///
+/// the aad is not stored in the encrypted data and must be provided again
+/// when decrypting
+pub fn encrypt_data_aad<D, A>(key: &Key, nonce: &Nonce, data: D, aad: A) -> Result<Vec<u8>>
+where
+    D: AsRef<[u8]>,
+    A: AsRef<[u8]>,
+{
+    let cipher = match XChaCha20Poly1305::new_from_slice(key.as_bytes()) {
         Ok(c) => c,
         Err(err) => {
             return Err(Error::new(ErrorKind::ChaChaError)
@@ -86,7 +185,12 @@ where
         }
     };
 
-    cipher.encrypt(nonce.into(), data.as_ref()).map_err(|err| {
+    let payload = Payload {
+        msg: data.as_ref(),
+        aad: aad.as_ref(),
+    };
+
+    cipher.encrypt(nonce.into(), payload).map_err(|err| {
         Error::new(ErrorKind::ChaChaError)
             .with_message("failed to encrypt requested data")
             .with_error(err)
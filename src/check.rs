@@ -0,0 +1,275 @@
+use serde_json::Value;
+
+use crate::types::{RawRecord, TotpRecord};
+
+/// parses an unparsed record
+///
+/// uses the same rules as reading a file so that a record that passes will
+/// always be readable. returns a description of the problem if the record is
+/// invalid
+pub fn parse_value(value: Value) -> Result<TotpRecord, String> {
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+/// finds the end of a json string starting at the given quote
+///
+/// returns the index of the closing quote
+fn json_string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut index = start + 1;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'"' => return Some(index),
+            _ => index += 1,
+        }
+    }
+
+    None
+}
+
+/// finds the end of a json value starting at the given index
+///
+/// objects and arrays are matched to their closing bracket while other
+/// values end at the next comma or closing bracket. returns the index after
+/// the value and if the value was terminated
+fn json_value_end(bytes: &[u8], start: usize) -> (usize, bool) {
+    let mut depth = 0usize;
+    let mut index = start;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'"' => match json_string_end(bytes, index) {
+                Some(end) => index = end,
+                None => return (bytes.len(), false),
+            },
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                if depth == 0 {
+                    return (index, true);
+                }
+
+                depth -= 1;
+
+                if depth == 0 {
+                    return (index + 1, true);
+                }
+            }
+            b',' if depth == 0 => return (index, true),
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    (bytes.len(), false)
+}
+
+/// attempts to read the records of a damaged json file
+///
+/// the top level object is scanned for names and each value is parsed on its
+/// own so that a single damaged record does not prevent the others from
+/// being read. scanning stops at the end of the top level object or the end
+/// of the data.
+pub fn scan_json(text: &str) -> Vec<RawRecord> {
+    let bytes = text.as_bytes();
+    let mut rtn = Vec::new();
+
+    let Some(mut index) = bytes.iter().position(|b| *b == b'{').map(|i| i + 1) else {
+        return rtn;
+    };
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'}' => break,
+            b'"' => {}
+            _ => {
+                index += 1;
+                continue;
+            }
+        }
+
+        let Some(name_end) = json_string_end(bytes, index) else {
+            break;
+        };
+
+        let Ok(name) = serde_json::from_str::<String>(&text[index..=name_end]) else {
+            index = name_end + 1;
+            continue;
+        };
+
+        let Some(colon) = bytes[name_end + 1..]
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .map(|i| name_end + 1 + i)
+            .filter(|i| bytes[*i] == b':')
+        else {
+            index = name_end + 1;
+            continue;
+        };
+
+        let (value_end, terminated) = json_value_end(bytes, colon + 1);
+        let slice = &text[colon + 1..value_end];
+
+        let value = if terminated {
+            serde_json::from_str(slice).map_err(|err| err.to_string())
+        } else {
+            Err(String::from("record is incomplete"))
+        };
+
+        rtn.push(RawRecord { name, value });
+
+        index = value_end;
+    }
+
+    rtn
+}
+
+/// attempts to read the records of a damaged yaml file
+///
+/// the file is split into blocks that start with an unindented line and each
+/// block is parsed on its own so that a single damaged record does not
+/// prevent the others from being read.
+pub fn scan_yaml(text: &str) -> Vec<RawRecord> {
+    let mut blocks: Vec<String> = Vec::new();
+
+    for line in text.lines() {
+        let is_start = line
+            .chars()
+            .next()
+            .map(|c| !c.is_whitespace() && c != '#')
+            .unwrap_or(false);
+
+        if line.starts_with("---") || line.starts_with("...") {
+            continue;
+        }
+
+        if is_start {
+            blocks.push(String::new());
+        }
+
+        if let Some(block) = blocks.last_mut() {
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+
+    let mut rtn = Vec::with_capacity(blocks.len());
+
+    for block in blocks {
+        match serde_yml::from_str::<serde_json::Map<String, Value>>(&block) {
+            Ok(dict) => {
+                for (name, value) in dict {
+                    rtn.push(RawRecord {
+                        name,
+                        value: Ok(value),
+                    });
+                }
+            }
+            Err(err) => {
+                let first = block.lines().next().unwrap_or_default();
+                let name = first
+                    .split_once(':')
+                    .map(|(n, _)| n)
+                    .unwrap_or(first)
+                    .trim()
+                    .trim_matches(|c| c == '"' || c == '\'');
+
+                rtn.push(RawRecord {
+                    name: name.to_owned(),
+                    value: Err(err.to_string()),
+                });
+            }
+        }
+    }
+
+    rtn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(records: &[RawRecord]) -> Vec<&str> {
+        records.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_valid() {
+        let record = parse_value(serde_json::json!({
+            "secret": [1, 2, 3],
+            "digits": 8,
+            "issuer": null,
+            "username": null,
+        }))
+        .unwrap();
+
+        assert_eq!(record.digits.get(), 8);
+        assert_eq!(record.step.get(), 30);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for value in [
+            serde_json::json!([]),
+            serde_json::json!({"issuer": null, "username": null}),
+            serde_json::json!({"secret": [], "issuer": null, "username": null}),
+            serde_json::json!({"secret": [256], "issuer": null, "username": null}),
+            serde_json::json!({"secret": [1], "digits": 3, "issuer": null, "username": null}),
+            serde_json::json!({"secret": [1], "step": 0, "issuer": null, "username": null}),
+            serde_json::json!({"secret": [1], "algo": "MD4", "issuer": null, "username": null}),
+            serde_json::json!({"secret": [1], "tags": "a", "issuer": null, "username": null}),
+        ] {
+            assert!(
+                parse_value(value.clone()).is_err(),
+                "{} was accepted",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn scan_json_valid() {
+        let records = scan_json(r#"{"a": {"secret": [1]}, "b,}": {"x": "}"}}"#);
+
+        assert_eq!(names(&records), vec!["a", "b,}"]);
+        assert!(records.iter().all(|r| r.value.is_ok()));
+        assert_eq!(records[1].value.as_ref().unwrap()["x"], "}");
+    }
+
+    #[test]
+    fn scan_json_damaged() {
+        let records =
+            scan_json(r#"{"a": {"secret": [1]}, "b": {"secret": [1,, 2]}, "c": 5, "d": {"sec"#);
+
+        assert_eq!(names(&records), vec!["a", "b", "c", "d"]);
+        assert!(records[0].value.is_ok());
+        assert!(records[1].value.is_err());
+        assert!(records[2].value.is_ok());
+        assert!(records[3].value.is_err());
+    }
+
+    #[test]
+    fn scan_json_empty() {
+        assert!(scan_json("").is_empty());
+        assert!(scan_json("not json").is_empty());
+        assert!(scan_json("{}").is_empty());
+    }
+
+    #[test]
+    fn scan_yaml_damaged() {
+        let text = "---\na:\n  secret: [1]\nb:\n  secret: [1\n  digits: 6\n# comment\nc:\n  secret:\n    - 2\n";
+        let records = scan_yaml(text);
+
+        assert_eq!(names(&records), vec!["a", "b", "c"]);
+        assert!(records[0].value.is_ok());
+        assert!(records[1].value.is_err());
+        assert_eq!(records[2].value.as_ref().unwrap()["secret"][0], 2);
+    }
+
+    #[test]
+    fn scan_yaml_empty() {
+        assert!(scan_yaml("").is_empty());
+        assert!(scan_yaml("# only a comment\n").is_empty());
+    }
+}
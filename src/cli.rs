@@ -1,9 +1,42 @@
 use std::io::Write;
+use std::num::NonZeroU64;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
+use crate::agent;
+use crate::chacha;
 use crate::error;
 use crate::otp;
 use crate::path;
+use crate::types;
+use crate::{Clock, SecretBytes, SecretString, System};
+
+/// the offset in seconds applied to the clock for all records
+static TIME_OFFSET: OnceLock<i64> = OnceLock::new();
+
+/// sets the global clock offset in seconds
+///
+/// only the first call will set the value
+pub fn set_time_offset(offset: i64) {
+    let _ = TIME_OFFSET.set(offset);
+}
+
+/// retrieves the current UNIX EPOCH or an error if the clock is not available
+pub fn unix_epoch_sec_now() -> error::Result<u64> {
+    System.now().ok_or_else(|| {
+        error::Error::new(error::ErrorKind::IoError).with_message("system clock is not available")
+    })
+}
+
+/// retrieves the current UNIX EPOCH used to generate codes
+///
+/// the global clock offset is applied here. the offset of each record is
+/// applied by the record when generating a code
+pub fn code_time_now() -> error::Result<u64> {
+    let now = unix_epoch_sec_now()?;
+
+    Ok(now.saturating_add_signed(TIME_OFFSET.get().copied().unwrap_or(0)))
+}
 
 #[derive(Debug, clap::Args)]
 pub struct RecordFile {
@@ -30,35 +63,124 @@ impl RecordFile {
             Ok(cwd.join("records.totp"))
         }
     }
+
+    /// opens the specified file
+    ///
+    /// see [open_file] for how encrypted files are handled
+    pub fn open(&self) -> error::Result<types::TotpFile> {
+        open_file(self.get_file()?)
+    }
+}
+
+/// prompts the user for the secret of an encrypted file
+fn prompt_file_key() -> error::Result<chacha::Key> {
+    let secret = get_secret("secret")?;
+    chacha::make_key(&secret)
+}
+
+/// calls open with a function that retrieves the key for an encrypted file
+///
+/// the agent is checked first in case the file has already been unlocked
+/// otherwise the user is prompted for the secret. if the key from the agent
+/// fails to decrypt the file, such as when the file was created again with a
+/// different secret, the key is removed from the agent and open is called
+/// again prompting for the secret
+pub fn with_file_key<T, F>(path: &std::path::Path, open: F) -> error::Result<T>
+where
+    F: Fn(&mut dyn FnMut(&std::path::Path) -> error::Result<chacha::Key>) -> error::Result<T>,
+{
+    let mut from_agent = false;
+
+    let result = open(&mut |path| {
+        if let Some(key) = agent::get_key(path) {
+            from_agent = true;
+
+            Ok(key)
+        } else {
+            prompt_file_key()
+        }
+    });
+
+    match result {
+        Err(err) if from_agent && matches!(err.kind, error::ErrorKind::ChaChaError) => {
+            let _ = agent::remove_key(path);
+
+            open(&mut |_| prompt_file_key())
+        }
+        result => result,
+    }
+}
+
+/// opens a totp file from the given path
+///
+/// see [with_file_key] for how the key of encrypted files is retrieved
+pub fn open_file<P>(path: P) -> error::Result<types::TotpFile>
+where
+    P: AsRef<std::path::Path>,
+{
+    with_file_key(path.as_ref(), |get_key| {
+        types::TotpFile::from_path_with(path.as_ref(), get_key)
+    })
+}
+
+/// the format used when printing records
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// human readable text
+    #[default]
+    Text,
+    /// a json array of objects
+    Json,
+    /// tab separated values with a header row
+    Tsv,
 }
 
 #[derive(Debug, Clone)]
-pub struct Base32(pub Vec<u8>);
+pub struct Base32(pub SecretBytes);
 
 impl std::str::FromStr for Base32 {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match data_encoding::BASE32.decode(s.as_bytes()) {
-            Ok(s) => Ok(Self(s)),
+            Ok(s) if s.is_empty() => Err("BASE32 string must not be empty"),
+            Ok(s) => Ok(Self(SecretBytes::new(s))),
             Err(_) => Err("invalid BASE32 string"),
         }
     }
 }
 
-impl From<Base32> for Vec<u8> {
+impl From<Base32> for SecretBytes {
     fn from(value: Base32) -> Self {
         value.0
     }
 }
 
+/// a key and value given as key=value
+#[derive(Debug, Clone)]
+pub struct KeyValue(pub String, pub String);
+
+impl std::str::FromStr for KeyValue {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, _)) if key.trim().is_empty() => Err("key must not be empty"),
+            Some((key, value)) => Ok(Self(key.trim().to_owned(), value.to_owned())),
+            None => Err("expected a value in the form key=value"),
+        }
+    }
+}
+
 /// parses a BASE32 encoded string
-pub fn parse_secret<S>(secret: S) -> error::Result<Vec<u8>>
+pub fn parse_secret<S>(secret: S) -> error::Result<SecretBytes>
 where
     S: AsRef<[u8]>,
 {
     match data_encoding::BASE32.decode(secret.as_ref()) {
-        Ok(s) => Ok(s),
+        Ok(s) if s.is_empty() => Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("key must not be empty")),
+        Ok(s) => Ok(SecretBytes::new(s)),
         Err(err) => Err(error::Error::new(error::ErrorKind::InvalidArgument)
             .with_message("key is an invalid base32 value")
             .with_error(err)),
@@ -78,26 +200,29 @@ where
     }
 }
 
-/// parses a string to a valid u32
-pub fn parse_digits<D>(digits: D) -> error::Result<u32>
+/// parses a string to valid [types::Digits]
+pub fn parse_digits<D>(digits: D) -> error::Result<types::Digits>
 where
     D: AsRef<str>,
 {
     if let Ok(parsed) = u32::from_str_radix(digits.as_ref(), 10) {
-        Ok(parsed)
+        types::Digits::new(parsed)
     } else {
         Err(error::Error::new(error::ErrorKind::InvalidArgument)
             .with_message("digits is not a valid unsiged integer"))
     }
 }
 
-/// parses a string to a valid u64
-pub fn parse_step<S>(step: S) -> error::Result<u64>
+/// parses a string to a valid non zero u64
+pub fn parse_step<S>(step: S) -> error::Result<NonZeroU64>
 where
     S: AsRef<str>,
 {
     if let Ok(parsed) = u64::from_str_radix(step.as_ref(), 10) {
-        Ok(parsed)
+        NonZeroU64::new(parsed).ok_or_else(|| {
+            error::Error::new(error::ErrorKind::InvalidArgument)
+                .with_message("step/period must be greater than zero")
+        })
     } else {
         return Err(error::Error::new(error::ErrorKind::InvalidArgument)
             .with_message("step/period is not a valid unsiged integer"));
@@ -110,12 +235,22 @@ where
     M: AsRef<str>,
 {
     let stdin = std::io::stdin();
-    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
     let mut input = String::new();
 
-    write!(&mut stdout, "{}: ", message.as_ref())?;
-    stdout.flush()?;
+    write!(&mut stderr, "{}: ", message.as_ref())?;
+    stderr.flush()?;
     stdin.read_line(&mut input)?;
 
     Ok(input)
 }
+
+/// prompts the user for a secret value with a given message
+///
+/// the returned value is zeroed when dropped
+pub fn get_secret<M>(message: M) -> error::Result<SecretString>
+where
+    M: AsRef<str>,
+{
+    get_input(message).map(SecretString::new)
+}
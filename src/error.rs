@@ -3,6 +3,7 @@
 pub enum ErrorKind {
     InvalidExtension,
     InvalidArgument,
+    NotFound,
     IoError,
     JsonError,
     YamlError,
@@ -10,6 +11,7 @@ pub enum ErrorKind {
     UrlError,
     ChaChaError,
     RandError,
+    IntegrityError,
 }
 
 impl std::fmt::Display for ErrorKind {
@@ -17,6 +19,7 @@ impl std::fmt::Display for ErrorKind {
         match self {
             ErrorKind::InvalidExtension => f.write_str("InvalidExtension"),
             ErrorKind::InvalidArgument => f.write_str("InvalidArgument"),
+            ErrorKind::NotFound => f.write_str("NotFound"),
             ErrorKind::IoError => f.write_str("IoError"),
             ErrorKind::JsonError => f.write_str("JsonError"),
             ErrorKind::YamlError => f.write_str("YamlError"),
@@ -24,6 +27,7 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::UrlError => f.write_str("UrlError"),
             ErrorKind::ChaChaError => f.write_str("ChaChaError"),
             ErrorKind::RandError => f.write_str("RandError"),
+            ErrorKind::IntegrityError => f.write_str("IntegrityError"),
         }
     }
 }
@@ -35,6 +39,7 @@ type BoxDynError = Box<dyn std::error::Error + Send + Sync>;
 /// these errors are not really meant to be handled and more for just
 /// indicating that there was an error. capable of storing a message
 /// and the error the created the struct if provided
+#[derive(Debug)]
 pub struct Error {
     pub kind: ErrorKind,
     pub message: Option<String>,
@@ -105,6 +110,7 @@ impl From<chacha20poly1305::Error> for Error {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<rand::rand_core::OsError> for Error {
     fn from(err: rand::rand_core::OsError) -> Self {
         Error::new(ErrorKind::RandError).with_error(err)
@@ -120,6 +126,6 @@ pub mod build {
         msg.push_str(&name);
         msg.push('"');
 
-        return Error::new(ErrorKind::InvalidArgument).with_message(msg);
+        return Error::new(ErrorKind::NotFound).with_message(msg);
     }
 }
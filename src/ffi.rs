@@ -0,0 +1,311 @@
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::error::{Error, ErrorKind};
+use crate::types::TotpFile;
+use crate::util;
+
+pub const TOTP_OK: c_int = 0;
+pub const TOTP_ERR_INVALID_EXTENSION: c_int = 1;
+pub const TOTP_ERR_INVALID_ARGUMENT: c_int = 2;
+pub const TOTP_ERR_IO: c_int = 3;
+pub const TOTP_ERR_JSON: c_int = 4;
+pub const TOTP_ERR_YAML: c_int = 5;
+pub const TOTP_ERR_MAC: c_int = 6;
+pub const TOTP_ERR_URL: c_int = 7;
+pub const TOTP_ERR_CHACHA: c_int = 8;
+pub const TOTP_ERR_RAND: c_int = 9;
+pub const TOTP_ERR_INTEGRITY: c_int = 10;
+pub const TOTP_ERR_NULL_POINTER: c_int = 11;
+pub const TOTP_ERR_NOT_FOUND: c_int = 12;
+pub const TOTP_ERR_INVALID_UTF8: c_int = 13;
+pub const TOTP_ERR_PANIC: c_int = 14;
+
+/// maps an [ErrorKind] to the error code returned to C
+fn error_code(err: &Error) -> c_int {
+    match err.kind {
+        ErrorKind::InvalidExtension => TOTP_ERR_INVALID_EXTENSION,
+        ErrorKind::InvalidArgument => TOTP_ERR_INVALID_ARGUMENT,
+        ErrorKind::NotFound => TOTP_ERR_NOT_FOUND,
+        ErrorKind::IoError => TOTP_ERR_IO,
+        ErrorKind::JsonError => TOTP_ERR_JSON,
+        ErrorKind::YamlError => TOTP_ERR_YAML,
+        ErrorKind::MacError => TOTP_ERR_MAC,
+        ErrorKind::UrlError => TOTP_ERR_URL,
+        ErrorKind::ChaChaError => TOTP_ERR_CHACHA,
+        ErrorKind::RandError => TOTP_ERR_RAND,
+        ErrorKind::IntegrityError => TOTP_ERR_INTEGRITY,
+    }
+}
+
+/// runs the body of an exported function, returning the given value if it
+/// panics
+///
+/// unwinding across the C boundary is undefined behavior so every exported
+/// function must go through this
+fn guard<T, F>(on_panic: T, body: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or(on_panic)
+}
+
+/// an opened totp file with the names of the records in sorted order
+///
+/// the names are kept so that they can be borrowed by C until the vault is
+/// freed
+pub struct TotpVault {
+    file: TotpFile,
+    names: Vec<CString>,
+}
+
+/// reads a C string into a str
+unsafe fn read_str<'a>(ptr: *const c_char) -> Result<&'a str, c_int> {
+    if ptr.is_null() {
+        return Err(TOTP_ERR_NULL_POINTER);
+    }
+
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| TOTP_ERR_INVALID_UTF8)
+}
+
+/// returns a static description of the given error code
+#[no_mangle]
+pub extern "C" fn totp_error_string(code: c_int) -> *const c_char {
+    guard(c"unknown error".as_ptr(), || {
+        let msg: &'static CStr = match code {
+            TOTP_OK => c"ok",
+            TOTP_ERR_INVALID_EXTENSION => c"invalid file extension",
+            TOTP_ERR_INVALID_ARGUMENT => c"invalid argument",
+            TOTP_ERR_IO => c"io error",
+            TOTP_ERR_JSON => c"json error",
+            TOTP_ERR_YAML => c"yaml error",
+            TOTP_ERR_MAC => c"mac error",
+            TOTP_ERR_URL => c"url error",
+            TOTP_ERR_CHACHA => c"encryption error",
+            TOTP_ERR_RAND => c"random number error",
+            TOTP_ERR_INTEGRITY => c"integrity error",
+            TOTP_ERR_NULL_POINTER => c"null pointer",
+            TOTP_ERR_NOT_FOUND => c"record not found",
+            TOTP_ERR_INVALID_UTF8 => c"invalid utf-8 string",
+            TOTP_ERR_PANIC => c"internal error",
+            _ => c"unknown error",
+        };
+
+        msg.as_ptr()
+    })
+}
+
+/// opens a totp file with the given passphrase
+///
+/// the passphrase is only used if the file is encrypted and is used exactly
+/// as given. on success the vault is written to out and must be freed with
+/// [totp_vault_free]
+///
+/// # Safety
+///
+/// path and passphrase must be valid nul terminated strings and out must be
+/// a valid pointer to write to
+#[no_mangle]
+pub unsafe extern "C" fn totp_vault_open(
+    path: *const c_char,
+    passphrase: *const c_char,
+    out: *mut *mut TotpVault,
+) -> c_int {
+    guard(TOTP_ERR_PANIC, || {
+        if out.is_null() {
+            return TOTP_ERR_NULL_POINTER;
+        }
+
+        let path = match read_str(path) {
+            Ok(v) => v,
+            Err(code) => return code,
+        };
+        let passphrase = match read_str(passphrase) {
+            Ok(v) => v,
+            Err(code) => return code,
+        };
+
+        let file = match TotpFile::from_path_secret(path, passphrase) {
+            Ok(file) => file,
+            Err(err) => return error_code(&err),
+        };
+
+        let mut names: Vec<&String> = file.records.keys().collect();
+        names.sort();
+
+        let Ok(names) = names
+            .into_iter()
+            .map(|name| CString::new(name.as_str()))
+            .collect::<Result<Vec<CString>, _>>()
+        else {
+            return TOTP_ERR_INVALID_ARGUMENT;
+        };
+
+        *out = Box::into_raw(Box::new(TotpVault { file, names }));
+
+        TOTP_OK
+    })
+}
+
+/// frees a vault returned by [totp_vault_open]
+///
+/// # Safety
+///
+/// vault must be null or a pointer returned by [totp_vault_open] that has
+/// not already been freed
+#[no_mangle]
+pub unsafe extern "C" fn totp_vault_free(vault: *mut TotpVault) {
+    guard((), || {
+        if !vault.is_null() {
+            drop(Box::from_raw(vault));
+        }
+    })
+}
+
+/// returns the number of records in the vault
+///
+/// # Safety
+///
+/// vault must be null or a valid pointer returned by [totp_vault_open]
+#[no_mangle]
+pub unsafe extern "C" fn totp_vault_len(vault: *const TotpVault) -> usize {
+    guard(0, || match vault.as_ref() {
+        Some(vault) => vault.names.len(),
+        None => 0,
+    })
+}
+
+/// returns the name of the record at the given index
+///
+/// names are sorted. the returned string is owned by the vault and is valid
+/// until the vault is freed. returns null if the index is out of range
+///
+/// # Safety
+///
+/// vault must be null or a valid pointer returned by [totp_vault_open]
+#[no_mangle]
+pub unsafe extern "C" fn totp_vault_name(vault: *const TotpVault, index: usize) -> *const c_char {
+    guard(std::ptr::null(), || {
+        match vault.as_ref().and_then(|v| v.names.get(index)) {
+            Some(name) => name.as_ptr(),
+            None => std::ptr::null(),
+        }
+    })
+}
+
+/// generates the code of a record for the given unix epoch
+///
+/// on success the code is written to out and must be freed with
+/// [totp_string_free]
+///
+/// # Safety
+///
+/// vault must be a valid pointer returned by [totp_vault_open], name must be
+/// a valid nul terminated string, and out must be a valid pointer to write to
+#[no_mangle]
+pub unsafe extern "C" fn totp_vault_code_at(
+    vault: *const TotpVault,
+    name: *const c_char,
+    time: u64,
+    out: *mut *mut c_char,
+) -> c_int {
+    guard(TOTP_ERR_PANIC, || {
+        let Some(vault) = vault.as_ref() else {
+            return TOTP_ERR_NULL_POINTER;
+        };
+
+        if out.is_null() {
+            return TOTP_ERR_NULL_POINTER;
+        }
+
+        let name = match read_str(name) {
+            Ok(v) => v,
+            Err(code) => return code,
+        };
+
+        let Some(record) = vault.file.records.get(name) else {
+            return TOTP_ERR_NOT_FOUND;
+        };
+
+        let code = match record.code_at(time) {
+            Ok(code) => code,
+            Err(err) => return error_code(&err),
+        };
+
+        match CString::new(code) {
+            Ok(code) => {
+                *out = code.into_raw();
+
+                TOTP_OK
+            }
+            Err(_) => TOTP_ERR_INVALID_ARGUMENT,
+        }
+    })
+}
+
+/// generates the current code of a record
+///
+/// the same as [totp_vault_code_at] using the current time
+///
+/// # Safety
+///
+/// same requirements as [totp_vault_code_at]
+#[no_mangle]
+pub unsafe extern "C" fn totp_vault_code(
+    vault: *const TotpVault,
+    name: *const c_char,
+    out: *mut *mut c_char,
+) -> c_int {
+    guard(TOTP_ERR_PANIC, || {
+        let Some(now) = util::unix_epoch_sec_now() else {
+            return TOTP_ERR_IO;
+        };
+
+        totp_vault_code_at(vault, name, now, out)
+    })
+}
+
+/// frees a string returned by this library
+///
+/// # Safety
+///
+/// string must be null or a pointer returned by this library that has not
+/// already been freed
+#[no_mangle]
+pub unsafe extern "C" fn totp_string_free(string: *mut c_char) {
+    guard((), || {
+        if !string.is_null() {
+            drop(CString::from_raw(string));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_panic() {
+        assert_eq!(
+            guard(TOTP_ERR_PANIC, || -> c_int { panic!("ffi") }),
+            TOTP_ERR_PANIC
+        );
+        assert_eq!(guard(TOTP_ERR_PANIC, || TOTP_OK), TOTP_OK);
+    }
+
+    #[test]
+    fn null_arguments() {
+        let mut out = std::ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                totp_vault_open(std::ptr::null(), std::ptr::null(), &mut out),
+                TOTP_ERR_NULL_POINTER
+            );
+            assert_eq!(totp_vault_len(std::ptr::null()), 0);
+            assert!(totp_vault_name(std::ptr::null(), 0).is_null());
+        }
+    }
+}
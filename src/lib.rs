@@ -0,0 +1,18 @@
+pub mod chacha;
+pub mod error;
+pub mod ffi;
+mod mac;
+pub mod ocra;
+pub mod otp;
+mod platform;
+mod secret;
+pub mod types;
+mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::{Error, ErrorKind, Result};
+pub use otp::{hotp, totp, verify_hotp, verify_totp, Algo};
+pub use platform::{Clock, FixedClock, Random, Storage, System};
+pub use secret::{SecretBytes, SecretString};
+pub use types::{Digits, TotpFile, TotpRecord};
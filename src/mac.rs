@@ -58,14 +58,26 @@ macro_rules! hmac_methods {
 
 hmac_methods!(make_sha1, one_off_sha1, one_off_verify_sha1, sha1::Sha1);
 hmac_methods!(
-    make_sha256,
-    one_off_sha256,
-    one_off_verify_sha256,
+    make_sha3_256,
+    one_off_sha3_256,
+    one_off_verify_sha3_256,
     sha3::Sha3_256
 );
 hmac_methods!(
-    make_sha512,
-    one_off_sha512,
-    one_off_verify_sha512,
+    make_sha3_512,
+    one_off_sha3_512,
+    one_off_verify_sha3_512,
     sha3::Sha3_512
 );
+hmac_methods!(
+    make_sha2_256,
+    one_off_sha2_256,
+    one_off_verify_sha2_256,
+    sha2::Sha256
+);
+hmac_methods!(
+    make_sha2_512,
+    one_off_sha2_512,
+    one_off_verify_sha2_512,
+    sha2::Sha512
+);
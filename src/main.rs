@@ -1,18 +1,49 @@
 use clap::Parser;
 
-mod chacha;
+use totp_cli::{chacha, error, ocra, otp, types, Clock, SecretBytes, SecretString, System};
+
+mod agent;
+mod check;
 mod cli;
-mod error;
-mod mac;
 mod ops;
-mod otp;
 mod path;
 mod print;
-mod types;
-mod util;
+mod select;
+mod tui;
+
+/// the exit status for the given kind of error
+///
+/// 2 is shared with invalid command line arguments reported by clap. the
+/// values are documented in the README and should not be changed
+fn exit_code(kind: &error::ErrorKind) -> i32 {
+    match kind {
+        error::ErrorKind::InvalidArgument => 2,
+        error::ErrorKind::NotFound => 3,
+        error::ErrorKind::IoError => 4,
+        error::ErrorKind::ChaChaError => 5,
+        error::ErrorKind::InvalidExtension => 6,
+        error::ErrorKind::JsonError => 7,
+        error::ErrorKind::YamlError => 8,
+        error::ErrorKind::IntegrityError => 9,
+        error::ErrorKind::UrlError => 10,
+        error::ErrorKind::MacError => 11,
+        error::ErrorKind::RandError => 12,
+    }
+}
 
 #[derive(Debug, Parser)]
 struct CliArgs {
+    /// seconds added to the clock when generating codes for all records
+    ///
+    /// applied in addition to the offset of each record
+    #[arg(
+        long,
+        global = true,
+        env = "TOTP_CLI_TIME_OFFSET",
+        allow_negative_numbers = true
+    )]
+    time_offset: Option<i64>,
+
     #[command(subcommand)]
     op: ops::OpCmd,
 }
@@ -20,15 +51,141 @@ struct CliArgs {
 fn main() {
     let args = CliArgs::parse();
 
+    if let Some(offset) = args.time_offset {
+        cli::set_time_offset(offset);
+    }
+
     if let Err(err) = ops::run(args.op) {
+        let code = exit_code(&err.kind);
+
         if let Some(msg) = err.message {
-            println!("{}: {}", err.kind, msg);
+            eprintln!("{}: {}", err.kind, msg);
         } else {
-            println!("{}", err.kind);
+            eprintln!("{}", err.kind);
         }
 
         if let Some(src) = err.source {
-            println!("{}", src);
+            eprintln!("{}", src);
         }
+
+        std::process::exit(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_offset_is_not_saved_to_records() {
+        let path =
+            std::env::temp_dir().join(format!("totp-cli-offset-{}.json", std::process::id()));
+        let file = path.to_str().unwrap();
+
+        std::fs::write(&path, "{}").unwrap();
+        std::env::set_var("TOTP_CLI_TIME_OFFSET", "-30");
+
+        let run = |args: &[&str]| {
+            let parsed = CliArgs::try_parse_from(args).unwrap();
+
+            assert_eq!(parsed.time_offset, Some(-30));
+
+            ops::run(parsed.op).map_err(|err| err.message).unwrap();
+        };
+
+        run(&[
+            "totp-cli",
+            "add",
+            "-n",
+            "a",
+            "-s",
+            "JBSWY3DPEHPK3PXP",
+            "-p",
+            file,
+        ]);
+        run(&[
+            "totp-cli",
+            "add",
+            "-n",
+            "b",
+            "-s",
+            "JBSWY3DPEHPK3PXP",
+            "--record-offset",
+            "15",
+            "-p",
+            file,
+        ]);
+        run(&["totp-cli", "edit", "-n", "b", "-i", "X", "-p", file]);
+
+        let records: types::TotpRecordDict =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(records["a"].time_offset, 0);
+        assert_eq!(records["b"].time_offset, 15);
+        assert_eq!(records["b"].issuer.as_deref(), Some("X"));
+    }
+
+    #[test]
+    fn rename_to_existing_name() {
+        let path =
+            std::env::temp_dir().join(format!("totp-cli-rename-{}.json", std::process::id()));
+        let file = path.to_str().unwrap();
+
+        std::fs::write(&path, "{}").unwrap();
+
+        let run = |args: &[&str]| ops::run(CliArgs::try_parse_from(args).unwrap().op);
+
+        for name in ["a", "b"] {
+            run(&[
+                "totp-cli",
+                "add",
+                "-n",
+                name,
+                "-s",
+                "JBSWY3DPEHPK3PXP",
+                "-p",
+                file,
+            ])
+            .unwrap();
+        }
+
+        let result = run(&[
+            "totp-cli",
+            "rename",
+            "--original",
+            "b",
+            "--renamed",
+            "a",
+            "-p",
+            file,
+        ]);
+
+        let read = || -> types::TotpRecordDict {
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+        };
+
+        assert!(matches!(
+            result.map_err(|err| err.kind),
+            Err(error::ErrorKind::InvalidArgument)
+        ));
+        assert_eq!(read().keys().collect::<Vec<_>>(), ["a", "b"]);
+
+        run(&[
+            "totp-cli",
+            "rename",
+            "--original",
+            "b",
+            "--renamed",
+            "c",
+            "-p",
+            file,
+        ])
+        .unwrap();
+
+        assert_eq!(read().keys().collect::<Vec<_>>(), ["a", "c"]);
+
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -0,0 +1,613 @@
+use serde::{Deserialize, Serialize};
+use sha1::Digest;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::mac;
+use crate::otp;
+
+/// length in bytes of the question after padding
+const QUESTION_LEN: usize = 128;
+/// the most characters a challenge can have
+///
+/// mutual challenge-response uses the client and server challenges
+/// together so a challenge can be longer than the length in the suite
+const MAX_CHALLENGE_LEN: usize = 128;
+
+/// the hash functions available to an ocra suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hash {
+    SHA1,
+    SHA256,
+    SHA512,
+}
+
+impl Hash {
+    fn parse(v: &str) -> Option<Hash> {
+        match v {
+            "SHA1" => Some(Hash::SHA1),
+            "SHA256" => Some(Hash::SHA256),
+            "SHA512" => Some(Hash::SHA512),
+            _ => None,
+        }
+    }
+
+    /// the length of the hash output in bytes
+    fn len(&self) -> usize {
+        match self {
+            Hash::SHA1 => 20,
+            Hash::SHA256 => 32,
+            Hash::SHA512 => 64,
+        }
+    }
+
+    /// hashes the given data
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::SHA1 => sha1::Sha1::digest(data).to_vec(),
+            Hash::SHA256 => sha2::Sha256::digest(data).to_vec(),
+            Hash::SHA512 => sha2::Sha512::digest(data).to_vec(),
+        }
+    }
+
+    /// runs the hmac for the given hash
+    fn hmac(&self, secret: &[u8], data: &[u8]) -> mac::Result<Vec<u8>> {
+        match self {
+            Hash::SHA1 => mac::one_off_sha1(secret, data),
+            Hash::SHA256 => mac::one_off_sha2_256(secret, data),
+            Hash::SHA512 => mac::one_off_sha2_512(secret, data),
+        }
+    }
+}
+
+/// the format of a challenge question
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionFormat {
+    Alphanumeric,
+    Numeric,
+    Hex,
+}
+
+/// an OCRA suite as defined in RFC 6287
+///
+/// parsed from the string form, for example "OCRA-1:HOTP-SHA1-6:QN08", which
+/// is also used when de/serializing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suite {
+    value: String,
+    pub hash: Hash,
+    pub digits: u32,
+    pub counter: bool,
+    pub question: QuestionFormat,
+    pub question_len: usize,
+    pub pin: Option<Hash>,
+    pub session_len: Option<usize>,
+    pub time_step: Option<u64>,
+}
+
+impl Suite {
+    /// parses a suite from its string form
+    pub fn parse<S>(value: S) -> std::result::Result<Suite, String>
+    where
+        S: Into<String>,
+    {
+        let value = value.into();
+        let mut parts = value.split(':');
+
+        let (Some(version), Some(function), Some(inputs), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(String::from(
+                "suite must have a version, crypto function, and data input",
+            ));
+        };
+
+        if version != "OCRA-1" {
+            return Err(format!("unknown ocra version \"{}\"", version));
+        }
+
+        let mut function_parts = function.split('-');
+
+        let (Some("HOTP"), Some(hash), Some(digits), None) = (
+            function_parts.next(),
+            function_parts.next(),
+            function_parts.next(),
+            function_parts.next(),
+        ) else {
+            return Err(format!("invalid crypto function \"{}\"", function));
+        };
+
+        let Some(hash) = Hash::parse(hash) else {
+            return Err(format!("unknown hash function \"{}\"", hash));
+        };
+
+        let digits = match digits.parse::<u32>() {
+            Ok(d) if d == 0 || (4..=10).contains(&d) => d,
+            _ => return Err(format!("invalid truncation length \"{}\"", digits)),
+        };
+
+        let mut counter = false;
+        let mut question = None;
+        let mut pin = None;
+        let mut session_len = None;
+        let mut time_step = None;
+
+        for input in inputs.split('-') {
+            if input == "C" {
+                counter = true;
+            } else if let Some(q) = input.strip_prefix('Q') {
+                let format = match q.get(..1) {
+                    Some("A") => QuestionFormat::Alphanumeric,
+                    Some("N") => QuestionFormat::Numeric,
+                    Some("H") => QuestionFormat::Hex,
+                    _ => return Err(format!("invalid question format \"{}\"", input)),
+                };
+
+                let len = match q[1..].parse::<usize>() {
+                    Ok(l) if (4..=64).contains(&l) && q.len() == 3 => l,
+                    _ => return Err(format!("invalid question length \"{}\"", input)),
+                };
+
+                question = Some((format, len));
+            } else if let Some(p) = input.strip_prefix('P') {
+                let Some(h) = Hash::parse(p) else {
+                    return Err(format!("unknown pin hash \"{}\"", input));
+                };
+
+                pin = Some(h);
+            } else if let Some(s) = input.strip_prefix('S') {
+                match s.parse::<usize>() {
+                    Ok(l) if l > 0 && l <= 512 && s.len() == 3 => {
+                        session_len = Some(l);
+                    }
+                    _ => return Err(format!("invalid session length \"{}\"", input)),
+                }
+            } else if let Some(t) = input.strip_prefix('T') {
+                let Some((amount, unit)) = t.split_at_checked(t.len().saturating_sub(1)) else {
+                    return Err(format!("invalid time step \"{}\"", input));
+                };
+
+                let (scale, max) = match unit {
+                    "S" => (1, 59),
+                    "M" => (60, 59),
+                    "H" => (3600, 48),
+                    _ => return Err(format!("invalid time step \"{}\"", input)),
+                };
+
+                match amount.parse::<u64>() {
+                    Ok(a) if a > 0 && a <= max => {
+                        time_step = Some(a * scale);
+                    }
+                    _ => return Err(format!("invalid time step \"{}\"", input)),
+                }
+            } else {
+                return Err(format!("unknown data input \"{}\"", input));
+            }
+        }
+
+        let Some((question, question_len)) = question else {
+            return Err(String::from("suite is missing a question input"));
+        };
+
+        Ok(Suite {
+            value,
+            hash,
+            digits,
+            counter,
+            question,
+            question_len,
+            pin,
+            session_len,
+            time_step,
+        })
+    }
+}
+
+impl std::str::FromStr for Suite {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Suite::parse(s)
+    }
+}
+
+impl std::fmt::Display for Suite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl Serialize for Suite {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Suite {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Suite::parse(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// the values provided to compute an ocra response
+///
+/// which values are required is determined by the suite. the pin is the
+/// hash of the pin, not the pin itself. time is the current unix epoch in
+/// seconds.
+pub struct Inputs<'a> {
+    pub challenge: &'a str,
+    pub counter: Option<u64>,
+    pub pin_hash: Option<&'a [u8]>,
+    pub session: Option<&'a [u8]>,
+    pub time: Option<u64>,
+}
+
+/// hashes a pin with the hash function required by the suite
+pub fn hash_pin(suite: &Suite, pin: &[u8]) -> Option<Vec<u8>> {
+    suite.pin.map(|h| h.digest(pin))
+}
+
+fn invalid<M>(msg: M) -> Error
+where
+    M: Into<String>,
+{
+    Error::new(ErrorKind::InvalidArgument).with_message(msg)
+}
+
+/// converts a decimal string to an uppercase hex string
+///
+/// the result has no leading zeros, "0" is returned for zero
+fn decimal_to_hex(decimal: &str) -> String {
+    let mut bytes: Vec<u8> = Vec::new();
+
+    for c in decimal.chars() {
+        let mut carry = c.to_digit(10).unwrap_or(0);
+
+        for byte in bytes.iter_mut().rev() {
+            let v = (*byte as u32) * 10 + carry;
+            *byte = (v & 0xff) as u8;
+            carry = v >> 8;
+        }
+
+        while carry > 0 {
+            bytes.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let encoded = data_encoding::HEXUPPER.encode(&bytes);
+    let trimmed = encoded.trim_start_matches('0');
+
+    if trimmed.is_empty() {
+        String::from("0")
+    } else {
+        trimmed.to_owned()
+    }
+}
+
+/// encodes the challenge into the padded question bytes
+///
+/// the challenge is converted to a hex string that is padded on the right
+/// with zeros to the full length of the question
+fn encode_question(suite: &Suite, challenge: &str) -> Result<Vec<u8>> {
+    if challenge.len() < 4 || challenge.len() > MAX_CHALLENGE_LEN {
+        return Err(invalid(format!(
+            "challenge must be between 4 and {} characters",
+            MAX_CHALLENGE_LEN
+        )));
+    }
+
+    let mut hex = match suite.question {
+        QuestionFormat::Numeric => {
+            if !challenge.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid("challenge must only contain digits"));
+            }
+
+            decimal_to_hex(challenge)
+        }
+        QuestionFormat::Hex => {
+            if !challenge.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid("challenge must only contain hex characters"));
+            }
+
+            challenge.to_ascii_uppercase()
+        }
+        QuestionFormat::Alphanumeric => {
+            if !challenge.is_ascii() {
+                return Err(invalid("challenge must only contain ascii characters"));
+            }
+
+            data_encoding::HEXUPPER.encode(challenge.as_bytes())
+        }
+    };
+
+    while hex.len() < QUESTION_LEN * 2 {
+        hex.push('0');
+    }
+
+    data_encoding::HEXUPPER
+        .decode(hex.as_bytes())
+        .map_err(|_| invalid("challenge is too long"))
+}
+
+/// left pads the given bytes with zeros to the desired length
+fn left_pad(bytes: &[u8], len: usize) -> Result<Vec<u8>> {
+    if bytes.len() > len {
+        return Err(invalid(format!(
+            "value must not be longer than {} bytes",
+            len
+        )));
+    }
+
+    let mut rtn = vec![0u8; len - bytes.len()];
+    rtn.extend_from_slice(bytes);
+
+    Ok(rtn)
+}
+
+/// computes the response for the given suite, secret, and inputs
+///
+/// the message is built from the suite, counter, question, pin hash, session
+/// information, and time step in that order as defined in RFC 6287
+pub fn respond(suite: &Suite, secret: &[u8], inputs: &Inputs<'_>) -> Result<String> {
+    let mut msg = Vec::with_capacity(suite.value.len() + 1 + 8 + QUESTION_LEN);
+    msg.extend_from_slice(suite.value.as_bytes());
+    msg.push(0);
+
+    if suite.counter {
+        let Some(counter) = inputs.counter else {
+            return Err(invalid("suite requires a counter"));
+        };
+
+        msg.extend_from_slice(&counter.to_be_bytes());
+    }
+
+    msg.extend(encode_question(suite, inputs.challenge)?);
+
+    if let Some(hash) = suite.pin {
+        let Some(pin_hash) = inputs.pin_hash else {
+            return Err(invalid("suite requires a pin"));
+        };
+
+        msg.extend(left_pad(pin_hash, hash.len())?);
+    }
+
+    if let Some(len) = suite.session_len {
+        let Some(session) = inputs.session else {
+            return Err(invalid("suite requires session information"));
+        };
+
+        msg.extend(left_pad(session, len)?);
+    }
+
+    if let Some(step) = suite.time_step {
+        let Some(time) = inputs.time else {
+            return Err(invalid("suite requires a time"));
+        };
+
+        msg.extend_from_slice(&(time / step).to_be_bytes());
+    }
+
+    let hash = suite.hash.hmac(secret, &msg)?;
+
+    if suite.digits == 0 {
+        return Ok(data_encoding::HEXLOWER.encode(&hash));
+    }
+
+    let binary = otp::truncate(&hash);
+
+    Ok(format!(
+        "{:0width$}",
+        binary % 10u64.pow(suite.digits),
+        width = suite.digits as usize
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: &[u8] = b"12345678901234567890";
+    const SEED32: &[u8] = b"12345678901234567890123456789012";
+    const SEED64: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    /// the time step of the RFC 6287 time based vectors in minutes
+    const TIME_MINUTES: u64 = 0x132d0b6;
+
+    fn response(suite: &str, secret: &[u8], challenge: &str, counter: Option<u64>) -> String {
+        let suite = Suite::parse(suite).unwrap();
+        let pin_hash = hash_pin(&suite, b"1234");
+        let inputs = Inputs {
+            challenge,
+            counter,
+            pin_hash: pin_hash.as_deref(),
+            session: None,
+            time: Some(TIME_MINUTES * 60),
+        };
+
+        respond(&suite, secret, &inputs).unwrap()
+    }
+
+    #[test]
+    fn one_way() {
+        let expected = [
+            "237653", "243178", "653583", "740991", "608993", "388898", "816933", "224598",
+            "750600", "294470",
+        ];
+
+        for (i, code) in expected.iter().enumerate() {
+            let challenge = i.to_string().repeat(8);
+
+            assert_eq!(
+                response("OCRA-1:HOTP-SHA1-6:QN08", SEED, &challenge, None),
+                *code
+            );
+        }
+
+        let expected = [
+            "65347737", "86775851", "78192410", "71565254", "10104329", "65983500", "70069104",
+            "91771096", "75011558", "08522129",
+        ];
+
+        for (i, code) in expected.iter().enumerate() {
+            assert_eq!(
+                response(
+                    "OCRA-1:HOTP-SHA256-8:C-QN08-PSHA1",
+                    SEED32,
+                    "12345678",
+                    Some(i as u64)
+                ),
+                *code
+            );
+        }
+
+        let expected = ["83238735", "01501458", "17957585", "86776967", "86807031"];
+
+        for (i, code) in expected.iter().enumerate() {
+            let challenge = i.to_string().repeat(8);
+
+            assert_eq!(
+                response("OCRA-1:HOTP-SHA256-8:QN08-PSHA1", SEED32, &challenge, None),
+                *code
+            );
+        }
+
+        let expected = [
+            "07016083", "63947962", "70123924", "25341727", "33203315", "34205738", "44343969",
+            "51946085", "20403879", "31409299",
+        ];
+
+        for (i, code) in expected.iter().enumerate() {
+            let challenge = i.to_string().repeat(8);
+
+            assert_eq!(
+                response(
+                    "OCRA-1:HOTP-SHA512-8:C-QN08",
+                    SEED64,
+                    &challenge,
+                    Some(i as u64)
+                ),
+                *code
+            );
+        }
+
+        let expected = ["95209754", "55907591", "22048402", "24218844", "36209546"];
+
+        for (i, code) in expected.iter().enumerate() {
+            let challenge = i.to_string().repeat(8);
+
+            assert_eq!(
+                response("OCRA-1:HOTP-SHA512-8:QN08-T1M", SEED64, &challenge, None),
+                *code
+            );
+        }
+    }
+
+    #[test]
+    fn mutual() {
+        let vectors: [(&str, &[u8], &str, [&str; 5]); 4] = [
+            (
+                "OCRA-1:HOTP-SHA256-8:QA08",
+                SEED32,
+                "CLI2222{}SRV1111{}",
+                ["28247970", "01984843", "65387857", "03351211", "83412541"],
+            ),
+            (
+                "OCRA-1:HOTP-SHA256-8:QA08",
+                SEED32,
+                "SRV1111{}CLI2222{}",
+                ["15510767", "90175646", "33777207", "95285278", "28934924"],
+            ),
+            (
+                "OCRA-1:HOTP-SHA512-8:QA08",
+                SEED64,
+                "CLI2222{}SRV1111{}",
+                ["79496648", "76831980", "12250499", "90856481", "12761449"],
+            ),
+            (
+                "OCRA-1:HOTP-SHA512-8:QA08-PSHA1",
+                SEED64,
+                "SRV1111{}CLI2222{}",
+                ["18806276", "70020315", "01600026", "18951020", "32528969"],
+            ),
+        ];
+
+        for (suite, secret, format, expected) in vectors {
+            for (i, code) in expected.iter().enumerate() {
+                let challenge = format.replace("{}", &i.to_string());
+
+                assert_eq!(response(suite, secret, &challenge, None), *code);
+            }
+        }
+    }
+
+    #[test]
+    fn signature() {
+        let expected = ["53095496", "04110475", "31331128", "76028668", "46554205"];
+
+        for (i, code) in expected.iter().enumerate() {
+            let challenge = format!("SIG1{}000", i);
+
+            assert_eq!(
+                response("OCRA-1:HOTP-SHA256-8:QA08", SEED32, &challenge, None),
+                *code
+            );
+        }
+
+        let expected = ["77537423", "31970405", "10235557", "95213541", "65360607"];
+
+        for (i, code) in expected.iter().enumerate() {
+            let challenge = format!("SIG1{}00000", i);
+
+            assert_eq!(
+                response("OCRA-1:HOTP-SHA512-8:QA10-T1M", SEED64, &challenge, None),
+                *code
+            );
+        }
+    }
+
+    #[test]
+    fn challenge_length() {
+        let suite = Suite::parse("OCRA-1:HOTP-SHA1-6:QA08").unwrap();
+
+        assert!(encode_question(&suite, "abc").is_err());
+        assert!(encode_question(&suite, &"a".repeat(128)).is_ok());
+        assert!(encode_question(&suite, &"a".repeat(129)).is_err());
+
+        let suite = Suite::parse("OCRA-1:HOTP-SHA1-6:QN08").unwrap();
+
+        assert!(encode_question(&suite, &"9".repeat(128)).is_ok());
+        assert!(encode_question(&suite, "1234abcd").is_err());
+    }
+
+    #[test]
+    fn parse_suites() {
+        let suite = Suite::parse("OCRA-1:HOTP-SHA512-8:C-QH40-PSHA256-S064-T30S").unwrap();
+
+        assert_eq!(suite.hash, Hash::SHA512);
+        assert_eq!(suite.digits, 8);
+        assert!(suite.counter);
+        assert_eq!(suite.question, QuestionFormat::Hex);
+        assert_eq!(suite.question_len, 40);
+        assert_eq!(suite.pin, Some(Hash::SHA256));
+        assert_eq!(suite.session_len, Some(64));
+        assert_eq!(suite.time_step, Some(30));
+
+        for invalid in [
+            "OCRA-2:HOTP-SHA1-6:QN08",
+            "OCRA-1:HOTP-MD5-6:QN08",
+            "OCRA-1:HOTP-SHA1-3:QN08",
+            "OCRA-1:HOTP-SHA1-6:C",
+            "OCRA-1:HOTP-SHA1-6:QX08",
+            "OCRA-1:HOTP-SHA1-6:QN08-T0S",
+            "OCRA-1:HOTP-SHA1-6",
+        ] {
+            assert!(Suite::parse(invalid).is_err(), "{} was accepted", invalid);
+        }
+    }
+}
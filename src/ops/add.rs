@@ -1,3 +1,5 @@
+use std::num::{NonZeroU64, NonZeroUsize};
+
 use crate::cli;
 use crate::error;
 use crate::otp;
@@ -22,12 +24,34 @@ pub struct AddArgs {
     algo: otp::Algo,
 
     /// number of digits to generate for the codes
-    #[arg(short, long, default_value = "6")]
-    digits: u32,
+    ///
+    /// defaults to 6 or 5 for steam codes
+    #[arg(short, long)]
+    digits: Option<types::Digits>,
 
     /// the step between generating new codes
     #[arg(short = 't', long, default_value = "30")]
-    step: u64,
+    step: NonZeroU64,
+
+    /// how the codes are rendered
+    ///
+    /// one of decimal, steam, hex, or alphabet:<chars>
+    #[arg(short, long, default_value = "decimal")]
+    encoder: otp::Encoder,
+
+    /// splits the displayed code into groups of the given size
+    #[arg(short, long)]
+    group: Option<NonZeroUsize>,
+
+    /// the unix epoch that the step counting starts from
+    #[arg(long, default_value = "0")]
+    t0: u64,
+
+    /// seconds added to the clock when generating codes for this record
+    ///
+    /// applied in addition to the global --time-offset
+    #[arg(long, default_value = "0", allow_negative_numbers = true)]
+    record_offset: i64,
 
     /// the issuer that the code is for
     #[arg(short, long)]
@@ -37,6 +61,14 @@ pub struct AddArgs {
     #[arg(short, long)]
     username: Option<String>,
 
+    /// a tag used to group the record, can be repeated
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// the folder of the record, nested folders are separated by /
+    #[arg(long)]
+    folder: Option<String>,
+
     #[command(flatten)]
     file: cli::RecordFile,
 }
@@ -48,22 +80,39 @@ pub fn run(
         algo,
         digits,
         step,
+        encoder,
+        group,
+        t0,
+        record_offset,
         issuer,
         username,
+        tags,
+        folder,
         file,
     }: AddArgs,
 ) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
+    let mut totp_file = file.open()?;
 
-    let record = types::TotpRecord {
-        secret: secret.into(),
-        algo,
-        digits,
-        step,
-        issuer,
-        username,
+    let digits = match digits {
+        Some(d) => d,
+        None => types::Digits::new(encoder.default_digits())?,
     };
 
+    let mut record = types::TotpRecord::new(secret.into(), algo, digits, step)?;
+    record.encoder = encoder;
+    record.group = group;
+    record.t0 = t0;
+    record.time_offset = record_offset;
+    record.issuer = issuer;
+    record.username = username;
+    record.folder = folder.as_deref().and_then(types::normalize_folder);
+
+    for tag in tags {
+        if !record.tags.contains(&tag) {
+            record.tags.push(tag);
+        }
+    }
+
     print::print_totp_record(&name, &record);
 
     totp_file.records.insert(name, record);
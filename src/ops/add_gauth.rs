@@ -1,3 +1,5 @@
+use std::num::NonZeroU64;
+
 use crate::cli;
 use crate::error;
 use crate::otp;
@@ -26,16 +28,14 @@ pub struct AddGauthArgs {
 
 /// adds a new record to a totp file with google authenticator defaults
 pub fn run(AddGauthArgs { name, secret, file }: AddGauthArgs) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
-
-    let record = types::TotpRecord {
-        secret: secret.into(),
-        digits: 6,
-        step: 30,
-        algo: otp::Algo::SHA1,
-        issuer: None,
-        username: None,
-    };
+    let mut totp_file = file.open()?;
+
+    let record = types::TotpRecord::new(
+        secret.into(),
+        otp::Algo::SHA1,
+        types::Digits::new(6)?,
+        NonZeroU64::new(30).unwrap(),
+    )?;
 
     print::print_totp_record(&name, &record);
 
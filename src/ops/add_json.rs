@@ -6,13 +6,28 @@ use crate::types;
 /// adds a new record to a totp file using a json string
 ///
 /// the key value pairs of the json are as follows:
-/// secret: array u8
-/// algo: string "SHA1", "SHA256", "SHA512"
+/// secret: array u8 not empty
+/// algo: string "SHA1", "SHA256", "SHA512", "SHA2_256", "SHA2_512"
 ///       default "SHA1"
-/// digits: u32 default 6
-/// step: u64 default 30
+/// digits: u32 between 4 and 10, default 6
+/// step: u64 greater than 0, default 30
+/// encoder: string "Decimal", "Steam", "Hex" or {"Alphabet": string}
+///          default "Decimal"
+/// group: u64 greater than 0 optional
+/// t0: u64, default 0
+/// time_offset: i64 seconds, default 0
 /// issuer: string optional
-/// username: string optional",
+/// username: string optional
+/// tags: array string, default []
+/// folder: string optional
+/// notes: string optional
+/// url: string optional
+/// fields: object of strings, default {}
+/// recovery: array {"code": string, "used": bool}, default []
+/// created_at: u64 unix epoch optional, defaults to now
+/// updated_at: u64 unix epoch optional
+/// last_used_at: u64 unix epoch optional
+/// pinned: bool, default false
 #[derive(Debug, clap::Args)]
 pub struct AddJsonArgs {
     /// the name of the new record
@@ -39,9 +54,17 @@ pub fn run(
         file,
     }: AddJsonArgs,
 ) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
+    let mut record: types::TotpRecord = serde_json::from_str(&json).map_err(|err| {
+        error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message(format!("invalid json record: {}", err))
+    })?;
 
-    let record: types::TotpRecord = serde_json::from_str(&json)?;
+    let mut totp_file = file.open()?;
+
+    if record.created_at.is_none() {
+        record.created_at = cli::unix_epoch_sec_now().ok();
+        record.updated_at = record.created_at;
+    }
 
     print::print_totp_record(&name, &record);
 
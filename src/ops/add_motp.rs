@@ -0,0 +1,74 @@
+use std::num::NonZeroU64;
+
+use crate::cli;
+use crate::error;
+use crate::otp;
+use crate::print;
+use crate::types;
+use crate::{SecretBytes, SecretString};
+
+/// adds a new mobile-otp record to a totp file
+///
+/// codes are generated from the secret and a pin. if the pin is not stored
+/// with the record then it will be prompted for when generating codes
+#[derive(Debug, clap::Args)]
+pub struct AddMotpArgs {
+    /// the name of the new record
+    #[arg(short, long)]
+    name: String,
+
+    /// the init secret as a hex string
+    #[arg(short, long)]
+    secret: String,
+
+    /// stores the pin with the record
+    #[arg(long)]
+    pin: Option<String>,
+
+    /// the issuer that the code is for
+    #[arg(short, long)]
+    issuer: Option<String>,
+
+    /// the username associated with the codes
+    #[arg(short, long)]
+    username: Option<String>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(
+    AddMotpArgs {
+        name,
+        secret,
+        pin,
+        issuer,
+        username,
+        file,
+    }: AddMotpArgs,
+) -> error::Result<()> {
+    let Ok(secret) = data_encoding::HEXLOWER_PERMISSIVE.decode(secret.as_bytes()) else {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("secret is not a valid hex string"));
+    };
+
+    let mut totp_file = file.open()?;
+
+    let mut record = types::TotpRecord::new(
+        SecretBytes::new(secret),
+        otp::Algo::SHA1,
+        types::Digits::new(otp::MOTP_DIGITS)?,
+        NonZeroU64::new(otp::MOTP_STEP).unwrap(),
+    )?;
+    record.family = otp::Family::Motp;
+    record.pin = pin.map(SecretString::new);
+    record.issuer = issuer;
+    record.username = username;
+
+    print::print_totp_record(&name, &record);
+
+    totp_file.records.insert(name, record);
+    totp_file.update_file()?;
+
+    Ok(())
+}
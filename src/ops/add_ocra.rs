@@ -0,0 +1,68 @@
+use crate::cli;
+use crate::error;
+use crate::ocra;
+use crate::otp;
+use crate::print;
+use crate::types;
+
+/// adds a new ocra challenge-response record to a totp file
+///
+/// the record will not generate codes and is instead used with the respond
+/// operation to compute responses to challenges
+#[derive(Debug, clap::Args)]
+pub struct AddOcraArgs {
+    /// the name of the new record
+    #[arg(short, long)]
+    name: String,
+
+    /// a valid BASE32 string
+    #[arg(short, long)]
+    secret: cli::Base32,
+
+    /// the ocra suite to use
+    ///
+    /// example "OCRA-1:HOTP-SHA1-6:QN08"
+    #[arg(short = 'o', long)]
+    suite: ocra::Suite,
+
+    /// the issuer that the record is for
+    #[arg(short, long)]
+    issuer: Option<String>,
+
+    /// the username associated with the record
+    #[arg(short, long)]
+    username: Option<String>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(
+    AddOcraArgs {
+        name,
+        secret,
+        suite,
+        issuer,
+        username,
+        file,
+    }: AddOcraArgs,
+) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+
+    let mut record = types::TotpRecord::new(
+        secret.into(),
+        otp::Algo::SHA1,
+        types::Digits::new(otp::MIN_DIGITS)?,
+        std::num::NonZeroU64::MIN,
+    )?;
+    record.family = otp::Family::Ocra(suite);
+    record.issuer = issuer;
+    record.username = username;
+
+    print::print_totp_record(&name, &record);
+
+    totp_file.records.insert(name, record);
+    totp_file.update_file()?;
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::borrow::Borrow;
+use std::num::NonZeroU64;
 
 use crate::cli;
 use crate::error;
@@ -25,18 +26,12 @@ pub struct AddUrlArgs {
     file: cli::RecordFile,
 }
 
-/// adds a new record to a totp file using url format
-pub fn run(
-    AddUrlArgs {
-        mut name,
-        view_only,
-        url,
-        file,
-    }: AddUrlArgs,
-) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
-
-    let url = url::Url::parse(&url)?;
+/// parses an otpauth url into a record
+///
+/// returns the name of the record, which is the given name or the issuer in
+/// the path of the url if not provided, along with the record
+fn parse_url(url: &str, mut name: Option<String>) -> error::Result<(String, types::TotpRecord)> {
+    let url = url::Url::parse(url)?;
 
     if url.scheme() != "otpauth" {
         return Err(error::Error::new(error::ErrorKind::UrlError)
@@ -55,14 +50,13 @@ pub fn run(
     }
 
     let mut record_key = "Unknown".to_owned();
-    let mut record = types::TotpRecord {
-        secret: Vec::new(),
-        digits: 6,
-        step: 30,
-        algo: otp::Algo::SHA1,
-        issuer: None,
-        username: None,
-    };
+    let mut secret = None;
+    let mut digits = None;
+    let mut step = NonZeroU64::new(30).unwrap();
+    let mut algo = otp::Algo::SHA1;
+    let mut encoder = otp::Encoder::Decimal;
+    let mut issuer = None;
+    let mut username = None;
 
     if let Some(mut split) = url.path_segments() {
         if let Some(first) = split.next() {
@@ -76,8 +70,8 @@ pub fn run(
             };
 
             if let Some((n, u)) = parsed.split_once(':') {
-                record.issuer = Some(n.into());
-                record.username = Some(u.into());
+                issuer = Some(n.into());
+                username = Some(u.into());
 
                 if name.is_none() {
                     name = Some(n.to_owned());
@@ -97,21 +91,37 @@ pub fn run(
     for (key, value) in query {
         match key.borrow() {
             "secret" => {
-                record.secret = cli::parse_secret(value.as_bytes())?;
+                secret = Some(cli::parse_secret(value.as_bytes())?);
             }
             "digits" => {
-                record.digits = cli::parse_digits(value)?;
+                digits = Some(cli::parse_digits(value)?);
             }
             "step" | "period" => {
-                record.step = cli::parse_step(value)?;
+                step = cli::parse_step(value)?;
             }
             "algorithm" => {
-                record.algo = cli::parse_algo(value)?;
+                // otpauth urls always refer to SHA-2 while SHA256 and SHA512
+                // records use SHA-3
+                algo = match value.borrow() {
+                    "SHA256" => otp::Algo::SHA2_256,
+                    "SHA512" => otp::Algo::SHA2_512,
+                    _ => cli::parse_algo(value)?,
+                };
             }
+            "encoder" => match value.parse() {
+                Ok(e) => {
+                    encoder = e;
+                }
+                Err(err) => {
+                    return Err(
+                        error::Error::new(error::ErrorKind::InvalidArgument).with_message(err)
+                    )
+                }
+            },
             "issuer" => {
                 match percent_encoding::percent_decode_str(value.borrow()).decode_utf8() {
                     Ok(i) => {
-                        record.issuer = Some(i.into_owned());
+                        issuer = Some(i.into_owned());
                     }
                     Err(err) => {
                         return Err(error::Error::new(error::ErrorKind::UrlError)
@@ -126,6 +136,48 @@ pub fn run(
         }
     }
 
+    let Some(secret) = secret else {
+        return Err(
+            error::Error::new(error::ErrorKind::UrlError).with_message("no secret provided in url")
+        );
+    };
+
+    // steam guard urls are standard totp urls that can only be identified by
+    // the issuer or the non-standard encoder parameter
+    if issuer
+        .as_ref()
+        .map(|i: &String| i.eq_ignore_ascii_case("steam"))
+        .unwrap_or(false)
+    {
+        encoder = otp::Encoder::Steam;
+    }
+
+    let digits = match digits {
+        Some(d) => d,
+        None => types::Digits::new(encoder.default_digits())?,
+    };
+
+    let mut record = types::TotpRecord::new(secret, algo, digits, step)?;
+    record.encoder = encoder;
+    record.issuer = issuer;
+    record.username = username;
+
+    Ok((record_key, record))
+}
+
+/// adds a new record to a totp file using url format
+pub fn run(
+    AddUrlArgs {
+        name,
+        view_only,
+        url,
+        file,
+    }: AddUrlArgs,
+) -> error::Result<()> {
+    let (record_key, record) = parse_url(&url, name)?;
+
+    let mut totp_file = file.open()?;
+
     print::print_totp_record(&record_key, &record);
 
     if !view_only {
@@ -135,3 +187,77 @@ pub fn run(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_standard() {
+        let (name, record) = parse_url(
+            "otpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXP&issuer=Example",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(name, "Example");
+        assert_eq!(&*record.secret, b"Hello!\xde\xad\xbe\xef");
+        assert_eq!(record.issuer.as_deref(), Some("Example"));
+        assert_eq!(record.username.as_deref(), Some("alice@google.com"));
+        assert_eq!(record.digits.get(), 6);
+        assert_eq!(record.step.get(), 30);
+        assert_eq!(record.encoder, otp::Encoder::Decimal);
+    }
+
+    #[test]
+    fn parse_options() {
+        let (name, record) = parse_url(
+            "otpauth://totp/ACME%20Co:john?secret=JBSWY3DPEHPK3PXP&algorithm=SHA512&digits=8&period=60",
+            Some(String::from("acme")),
+        )
+        .unwrap();
+
+        assert_eq!(name, "acme");
+        assert_eq!(record.issuer.as_deref(), Some("ACME Co"));
+        assert!(matches!(record.algo, otp::Algo::SHA2_512));
+        assert_eq!(record.digits.get(), 8);
+        assert_eq!(record.step.get(), 60);
+    }
+
+    #[test]
+    fn parse_steam() {
+        let (_, record) = parse_url(
+            "otpauth://totp/Steam:gaben?secret=JBSWY3DPEHPK3PXP&issuer=Steam",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(record.encoder, otp::Encoder::Steam);
+        assert_eq!(record.digits.get(), otp::STEAM_DIGITS);
+
+        let (_, record) = parse_url(
+            "otpauth://totp/a:b?secret=JBSWY3DPEHPK3PXP&encoder=steam",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(record.encoder, otp::Encoder::Steam);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for url in [
+            "not a url",
+            "https://totp/a:b?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://hotp/a:b?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://totp/a:b?issuer=a",
+            "otpauth://totp/a:b?secret=1",
+            "otpauth://totp/a:b?secret=JBSWY3DPEHPK3PXP&digits=2",
+            "otpauth://totp/a:b?secret=JBSWY3DPEHPK3PXP&period=0",
+            "otpauth://totp/a:b?secret=JBSWY3DPEHPK3PXP&algorithm=MD5",
+            "otpauth://totp/a:b?secret=JBSWY3DPEHPK3PXP&encoder=base64",
+        ] {
+            assert!(parse_url(url, None).is_err(), "{} was accepted", url);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::num::NonZeroU64;
+
+use crate::cli;
+use crate::error;
+use crate::otp;
+use crate::print;
+use crate::types;
+use crate::{SecretBytes, SecretString};
+
+/// adds a new yandex key record to a totp file
+///
+/// codes are generated from the secret and a pin. if the pin is not stored
+/// with the record then it will be prompted for when generating codes
+#[derive(Debug, clap::Args)]
+pub struct AddYandexArgs {
+    /// the name of the new record
+    #[arg(short, long)]
+    name: String,
+
+    /// the BASE32 secret provided by yandex
+    ///
+    /// padding is optional
+    #[arg(short, long)]
+    secret: String,
+
+    /// stores the pin with the record
+    #[arg(long)]
+    pin: Option<String>,
+
+    /// the issuer that the code is for
+    #[arg(short, long, default_value = "Yandex")]
+    issuer: String,
+
+    /// the username associated with the codes
+    #[arg(short, long)]
+    username: Option<String>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+/// decodes a BASE32 string that may be missing padding
+///
+/// yandex secrets have extra bits at the end so trailing bits are not
+/// checked
+fn decode_secret(secret: &str) -> error::Result<Vec<u8>> {
+    let mut spec = data_encoding::BASE32_NOPAD.specification();
+    spec.check_trailing_bits = false;
+
+    let encoding = spec.encoding().unwrap();
+    let trimmed = secret.trim_end_matches('=').to_ascii_uppercase();
+
+    match encoding.decode(trimmed.as_bytes()) {
+        Ok(bytes) if !bytes.is_empty() => Ok(bytes),
+        _ => Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("secret is not a valid BASE32 string")),
+    }
+}
+
+pub fn run(
+    AddYandexArgs {
+        name,
+        secret,
+        pin,
+        issuer,
+        username,
+        file,
+    }: AddYandexArgs,
+) -> error::Result<()> {
+    let secret = decode_secret(&secret)?;
+
+    let mut totp_file = file.open()?;
+
+    let mut record = types::TotpRecord::new(
+        SecretBytes::new(secret),
+        otp::Algo::SHA2_256,
+        types::Digits::new(otp::YANDEX_DIGITS)?,
+        NonZeroU64::new(30).unwrap(),
+    )?;
+    record.family = otp::Family::Yandex;
+    record.pin = pin.map(SecretString::new);
+    record.issuer = Some(issuer);
+    record.username = username;
+
+    print::print_totp_record(&name, &record);
+
+    totp_file.records.insert(name, record);
+    totp_file.update_file()?;
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use crate::agent;
+use crate::error;
+
+/// runs the background agent that holds unlocked keys
+///
+/// started automatically by the unlock operation
+#[derive(Debug, clap::Args)]
+pub struct AgentArgs {}
+
+pub fn run(_args: AgentArgs) -> error::Result<()> {
+    agent::serve()
+}
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use crate::chacha;
+use crate::check;
+use crate::cli;
+use crate::error;
+use crate::path;
+use crate::types;
+
+/// checks a totp file for damaged or invalid records
+///
+/// verifies the header and authentication tag of encrypted files and then
+/// validates each record, reporting any problems found. if a json or yaml
+/// file cannot be parsed then the readable records will be recovered where
+/// possible.
+#[derive(Debug, clap::Args)]
+pub struct CheckArgs {
+    /// writes all valid records to a new file
+    ///
+    /// the type of file is determined by the extension. a totp file will
+    /// prompt for a secret to encrypt the new file with and uses the same
+    /// layout as the checked file
+    #[arg(long)]
+    salvage: Option<PathBuf>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+/// attempts to recover records from a file that could not be parsed
+fn scan_file(path: &PathBuf) -> error::Result<Vec<types::RawRecord>> {
+    let text = String::from_utf8_lossy(&std::fs::read(path)?).into_owned();
+
+    match types::TotpFileType::from_path(path)? {
+        types::TotpFileType::JSON => Ok(check::scan_json(&text)),
+        types::TotpFileType::YAML => Ok(check::scan_yaml(&text)),
+        types::TotpFileType::TOTP => Ok(Vec::new()),
+    }
+}
+
+pub fn run(CheckArgs { salvage, file }: CheckArgs) -> error::Result<()> {
+    let path = file.get_file()?;
+
+    let read = cli::with_file_key(&path, |get_key| {
+        types::TotpFile::read_raw_with(&path, get_key)
+    });
+
+    let (layout, raw) = match read {
+        Ok(raw) => raw,
+        Err(err) => {
+            if matches!(
+                types::TotpFileType::from_path(&path)?,
+                types::TotpFileType::TOTP
+            ) {
+                return Err(err);
+            }
+
+            if let Some(src) = err.source {
+                println!("failed to parse file: {}", src);
+            } else {
+                println!("failed to parse file: {}", err.kind);
+            }
+
+            println!("attempting to recover records\n");
+
+            (types::TotpLayout::Blob, scan_file(&path)?)
+        }
+    };
+
+    let mut valid = types::TotpRecordDict::new();
+    let mut invalid = 0usize;
+
+    for types::RawRecord { name, value } in raw {
+        match value.and_then(check::parse_value) {
+            Ok(record) => {
+                println!("{}: ok", name);
+
+                valid.insert(name, record);
+            }
+            Err(problem) => {
+                invalid += 1;
+
+                println!("{}:", name);
+                println!("    - {}", problem);
+            }
+        }
+    }
+
+    println!("\n{} valid, {} invalid", valid.len(), invalid);
+
+    if let Some(salvage) = salvage {
+        let cwd = std::env::current_dir()?;
+        let salvage = path::normalize_from(&cwd, salvage);
+
+        if path::metadata(&salvage)?.is_some() {
+            return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+                .with_message("the salvage file already exists"));
+        }
+
+        let file_type = types::TotpFileType::from_path(&salvage)?;
+        let key = if matches!(file_type, types::TotpFileType::TOTP) {
+            let secret = cli::get_secret("salvage secret")?;
+            Some(chacha::make_key(secret)?)
+        } else {
+            None
+        };
+
+        let totp_file = types::TotpFile {
+            path: salvage,
+            file_type,
+            layout,
+            records: valid,
+            key,
+        };
+
+        totp_file.update_file()?;
+
+        println!("valid records written to {}", totp_file.path.display());
+    }
+
+    if invalid > 0 {
+        return Err(error::Error::new(error::ErrorKind::IntegrityError)
+            .with_message(format!("found problems with {} records", invalid)));
+    }
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -5,9 +6,10 @@ use clap::Args;
 
 use crate::cli;
 use crate::error;
+use crate::otp;
 use crate::print;
+use crate::select;
 use crate::types;
-use crate::util;
 
 /// prints generated codes to the terminal
 #[derive(Debug, Args)]
@@ -16,74 +18,278 @@ pub struct CodesArgs {
     #[arg(short, long)]
     watch: bool,
 
-    /// attempts to find the desired records in a given file
-    #[arg(short, long)]
-    name: Option<String>,
+    #[command(flatten)]
+    select: select::RecordSelect,
+
+    #[command(flatten)]
+    filter: select::RecordFilter,
+
+    #[command(flatten)]
+    sort: select::RecordSort,
+
+    /// records the current time as when the codes were last used
+    #[arg(long)]
+    track_usage: bool,
+
+    /// prints codes as a compact table that fits the terminal
+    ///
+    /// records are grouped by their step. when used with watch the table is
+    /// updated in place
+    #[arg(short, long, conflicts_with = "output")]
+    table: bool,
+
+    /// the format to print codes in
+    #[arg(short, long, default_value = "text", conflicts_with = "watch")]
+    output: cli::OutputFormat,
+
+    /// only prints the code of the selected record followed by a newline
+    #[arg(short, long, requires = "select", conflicts_with_all = ["watch", "output", "table"])]
+    raw: bool,
+
+    /// waits for the next period if fewer than the given seconds remain
+    ///
+    /// only used with raw
+    #[arg(short, long, requires = "raw")]
+    min_remaining: Option<u64>,
+
+    /// includes the BASE32 secret in json or tsv output
+    #[arg(long)]
+    show_secret: bool,
 
     #[command(flatten)]
     file: cli::RecordFile,
 }
 
-pub fn run(CodesArgs { watch, name, file }: CodesArgs) -> error::Result<()> {
-    let records = types::TotpFile::from_path(file.get_file()?)?.take_records();
+/// prompts for the pin of a record if it requires one and it is not stored
+fn prompt_pin(name: &str, record: &mut types::TotpRecord) -> error::Result<()> {
+    if record.family.requires_pin() && record.pin.is_none() {
+        record.pin = Some(cli::get_secret(format!("{} pin", name))?);
+    }
+
+    Ok(())
+}
+
+/// records the current time as when the codes of the records were last used
+///
+/// the codes are generated first so nothing is saved if any of them fail.
+/// challenge-response records do not generate codes and are skipped
+fn record_usage(
+    totp_file: &mut types::TotpFile,
+    dict: &types::TotpRecordDict,
+) -> error::Result<()> {
+    let now = cli::code_time_now()?;
+    let mut used = Vec::new();
+
+    for (name, record) in dict {
+        if let otp::Family::Ocra(_) = record.family {
+            continue;
+        }
+
+        record.code_at(now)?;
+        used.push(name);
+    }
+
+    let now = cli::unix_epoch_sec_now()?;
+
+    for name in used {
+        totp_file.records.get_mut(name).unwrap().last_used_at = Some(now);
+    }
+
+    totp_file.update_file()
+}
+
+/// prints only the code of a record
+///
+/// if fewer than min_remaining seconds are left in the current period then
+/// it will wait until the next period starts
+fn print_raw(record: &types::TotpRecord, min_remaining: Option<u64>) -> error::Result<()> {
+    let mut now = cli::code_time_now()?;
+
+    if let Some(min) = min_remaining {
+        let (_, left) = record.counter_at(now);
+
+        if left < min {
+            std::thread::sleep(Duration::from_secs(left));
+
+            now = cli::code_time_now()?.max(now + left);
+        }
+    }
+
+    println!("{}", record.code_at(now)?);
+
+    Ok(())
+}
+
+/// prints codes as a table
+///
+/// when watching, the screen is cleared once and the table is redrawn in
+/// place every second
+fn print_table(records: &[(&String, &types::TotpRecord)], watch: bool) -> error::Result<()> {
+    let mut stdout = std::io::stdout();
 
-    if let Some(name) = name {
-        let Some(record) = records.get(&name) else {
-            return Err(error::build::name_not_found(name));
-        };
+    if !watch {
+        let now = cli::code_time_now()?;
 
-        if watch {
-            let longest_key = 80;
+        for line in print::codes_table(records, now, print::terminal_width()) {
+            writeln!(stdout, "{}", line)?;
+        }
+
+        return Ok(());
+    }
+
+    write!(stdout, "\x1b[2J")?;
+
+    loop {
+        let start = Instant::now();
+        let now = cli::code_time_now()?;
 
-            loop {
-                let start = Instant::now();
+        let mut frame = String::from("\x1b[H");
 
-                print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+        // the last column is left empty so clearing the rest of a line does
+        // not erase the final character of a full width line
+        let width = print::terminal_width().saturating_sub(1);
 
-                print::print_totp_code(&name, record);
+        for line in print::codes_table(records, now, width) {
+            frame.push_str(&line);
+            frame.push_str("\x1b[K\n");
+        }
+
+        frame.push_str("\x1b[J");
 
-                let end = Instant::now();
-                let duration = end.duration_since(start);
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
 
-                println!(
-                    "\n{}\nfinished: {:#?}",
-                    util::pad_key("INFO", &longest_key),
-                    duration
-                );
+        if let Some(wait) = Duration::from_secs(1).checked_sub(start.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+}
 
-                if let Some(wait) = Duration::from_secs(1).checked_sub(duration) {
-                    std::thread::sleep(wait);
+/// prints the selected records in a machine readable format
+fn print_output(
+    records: &[(&String, &types::TotpRecord)],
+    output: cli::OutputFormat,
+    show_secret: bool,
+) -> error::Result<()> {
+    let now = cli::code_time_now()?;
+
+    let outputs: Vec<print::CodeOutput> = records
+        .iter()
+        .map(|(name, record)| print::CodeOutput::new(name, record, now, show_secret))
+        .collect();
+
+    print::print_codes_output(output, &outputs)
+}
+
+pub fn run(
+    CodesArgs {
+        watch,
+        select,
+        filter,
+        sort,
+        track_usage,
+        table,
+        output,
+        raw,
+        min_remaining,
+        show_secret,
+        file,
+    }: CodesArgs,
+) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+    let names = select::resolve(&totp_file.metadata(), &select, &filter, &sort)?;
+
+    if raw && names.len() != 1 {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("raw output requires a single record"));
+    }
+
+    // prompted pins are only set on copies so they are never saved to the
+    // file when tracking usage
+    let mut dict: types::TotpRecordDict = names
+        .iter()
+        .map(|name| (name.clone(), totp_file.records[name].clone()))
+        .collect();
+
+    for name in &names {
+        prompt_pin(name, dict.get_mut(name).unwrap())?;
+    }
+
+    if track_usage {
+        record_usage(&mut totp_file, &dict)?;
+    }
+
+    let records: Vec<(&String, &types::TotpRecord)> =
+        names.iter().map(|name| (name, &dict[name])).collect();
+
+    if raw {
+        return print_raw(records[0].1, min_remaining);
+    }
+
+    if !matches!(output, cli::OutputFormat::Text) {
+        return print_output(&records, output, show_secret);
+    }
+
+    if table {
+        return print_table(&records, watch);
+    }
+
+    match records.as_slice() {
+        [(name, record)] if !select.is_all() => {
+            if watch {
+                let longest_key = 80;
+
+                loop {
+                    let start = Instant::now();
+
+                    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+
+                    print::print_totp_code(name, record);
+
+                    let end = Instant::now();
+                    let duration = end.duration_since(start);
+
+                    println!(
+                        "\n{}\nfinished: {:#?}",
+                        print::pad_key("INFO", &longest_key),
+                        duration
+                    );
+
+                    if let Some(wait) = Duration::from_secs(1).checked_sub(duration) {
+                        std::thread::sleep(wait);
+                    }
                 }
+            } else {
+                print::print_totp_code(name, record);
             }
-        } else {
-            print::print_totp_code(&name, &record);
         }
-    } else {
-        let longest_key = util::longest_value(records.keys(), Some(80));
+        _ => {
+            let longest_key = print::longest_value(names.iter(), Some(80));
 
-        if watch {
-            loop {
-                let start = Instant::now();
+            if watch {
+                loop {
+                    let start = Instant::now();
 
-                print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+                    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
 
-                print::print_records_list(&records, &longest_key, &print::print_totp_code);
+                    print::print_records_list(&records, &longest_key, &print::print_totp_code);
 
-                let end = Instant::now();
-                let duration = end.duration_since(start);
+                    let end = Instant::now();
+                    let duration = end.duration_since(start);
 
-                println!(
-                    "\n{}\nfinished: {:#?}",
-                    util::pad_key("INFO", &longest_key),
-                    duration
-                );
+                    println!(
+                        "\n{}\nfinished: {:#?}",
+                        print::pad_key("INFO", &longest_key),
+                        duration
+                    );
 
-                if let Some(wait) = Duration::from_secs(1).checked_sub(duration) {
-                    std::thread::sleep(wait);
+                    if let Some(wait) = Duration::from_secs(1).checked_sub(duration) {
+                        std::thread::sleep(wait);
+                    }
                 }
+            } else {
+                print::print_records_list(&records, &longest_key, &print::print_totp_code);
             }
-        } else {
-            print::print_records_list(&records, &longest_key, &print::print_totp_code);
         }
     }
 
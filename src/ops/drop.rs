@@ -1,24 +1,61 @@
+use std::io::IsTerminal;
+
 use crate::cli;
 use crate::error;
-use crate::types;
+use crate::select;
 
-/// drops a record from a totp file
+/// drops records from a totp file
 #[derive(Debug, clap::Args)]
 pub struct DropArgs {
-    /// name of the record to drop
+    #[command(flatten)]
+    select: select::RecordSelect,
+
+    /// does not ask for confirmation
+    ///
+    /// confirmation is only skipped without this when a single record is
+    /// given with name
     #[arg(short, long)]
-    name: String,
+    yes: bool,
 
     #[command(flatten)]
     file: cli::RecordFile,
 }
 
-pub fn run(DropArgs { name, file }: DropArgs) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
+pub fn run(DropArgs { select, yes, file }: DropArgs) -> error::Result<()> {
+    if select.is_all() {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("no records were selected to drop"));
+    }
+
+    let mut totp_file = file.open()?;
+    let names = select.select(totp_file.records.keys())?;
+
+    if !yes && !select.is_exact() {
+        if !std::io::stdin().is_terminal() {
+            return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+                .with_message("stdin is not a terminal, use --yes or --name to drop records"));
+        }
+
+        let prompt = if let [name] = names.as_slice() {
+            format!("drop {}? [y/N]", name)
+        } else {
+            for name in &names {
+                eprintln!("{}", name);
+            }
+
+            format!("drop {} records? [y/N]", names.len())
+        };
+
+        let answer = cli::get_input(prompt)?;
+
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            return Ok(());
+        }
+    }
 
-    let Some(_record) = totp_file.records.remove(&name) else {
-        return Err(error::build::name_not_found(name));
-    };
+    for name in &names {
+        totp_file.records.shift_remove(name);
+    }
 
     totp_file.update_file()?;
 
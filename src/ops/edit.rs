@@ -1,3 +1,5 @@
+use std::num::{NonZeroU64, NonZeroUsize};
+
 use crate::cli;
 use crate::error;
 use crate::otp;
@@ -21,11 +23,30 @@ pub struct EditArgs {
 
     /// updates the digits
     #[arg(short, long)]
-    digits: Option<u32>,
+    digits: Option<types::Digits>,
 
     /// updates the step
     #[arg(short = 't', long)]
-    step: Option<u64>,
+    step: Option<NonZeroU64>,
+
+    /// updates the encoder
+    ///
+    /// one of decimal, steam, hex, or alphabet:<chars>
+    #[arg(short, long)]
+    encoder: Option<otp::Encoder>,
+
+    /// updates the group size of the displayed code. 0 removes grouping
+    #[arg(short, long)]
+    group: Option<usize>,
+
+    /// updates the unix epoch that the step counting starts from
+    #[arg(long)]
+    t0: Option<u64>,
+
+    /// updates the seconds added to the clock when generating codes for this
+    /// record
+    #[arg(long, allow_negative_numbers = true)]
+    record_offset: Option<i64>,
 
     /// updates the issuer
     #[arg(short, long)]
@@ -35,6 +56,28 @@ pub struct EditArgs {
     #[arg(short, long)]
     username: Option<String>,
 
+    /// updates the folder. an empty value removes the folder
+    ///
+    /// tags are changed with the tag operation
+    #[arg(long)]
+    folder: Option<String>,
+
+    /// updates the notes. an empty value removes the notes
+    #[arg(long)]
+    notes: Option<String>,
+
+    /// updates the login url. an empty value removes the url
+    #[arg(long)]
+    url: Option<String>,
+
+    /// sets a custom field given as key=value, can be repeated
+    #[arg(long = "field")]
+    fields: Vec<cli::KeyValue>,
+
+    /// removes a custom field by its key, can be repeated
+    #[arg(long = "remove-field")]
+    remove_fields: Vec<String>,
+
     #[command(flatten)]
     file: cli::RecordFile,
 }
@@ -46,12 +89,21 @@ pub fn run(
         algo,
         digits,
         step,
+        encoder,
+        group,
+        t0,
+        record_offset,
         issuer,
         username,
+        folder,
+        notes,
+        url,
+        fields,
+        remove_fields,
         file,
     }: EditArgs,
 ) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
+    let mut totp_file = file.open()?;
 
     if let Some(record) = totp_file.records.get_mut(&name) {
         if let Some(secret) = secret {
@@ -70,6 +122,22 @@ pub fn run(
             record.step = step;
         }
 
+        if let Some(encoder) = encoder {
+            record.encoder = encoder;
+        }
+
+        if let Some(group) = group {
+            record.group = NonZeroUsize::new(group);
+        }
+
+        if let Some(t0) = t0 {
+            record.t0 = t0;
+        }
+
+        if let Some(record_offset) = record_offset {
+            record.time_offset = record_offset;
+        }
+
         if issuer.is_some() {
             record.issuer = issuer;
         }
@@ -78,6 +146,28 @@ pub fn run(
             record.username = username;
         }
 
+        if let Some(folder) = folder {
+            record.folder = types::normalize_folder(&folder);
+        }
+
+        if let Some(notes) = notes {
+            record.notes = Some(notes).filter(|v| !v.is_empty());
+        }
+
+        if let Some(url) = url {
+            record.url = Some(url).filter(|v| !v.is_empty());
+        }
+
+        for key in remove_fields {
+            record.fields.remove(&key);
+        }
+
+        for cli::KeyValue(key, value) in fields {
+            record.fields.insert(key, value);
+        }
+
+        record.touch();
+
         print::print_totp_record(&name, record);
     } else {
         return Err(error::build::name_not_found(name));
@@ -0,0 +1,28 @@
+use crate::agent;
+use crate::cli;
+use crate::error;
+
+/// locks a file previously unlocked with the agent
+#[derive(Debug, clap::Args)]
+pub struct LockArgs {
+    /// locks all files held by the agent and stops it
+    #[arg(short, long)]
+    all: bool,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(LockArgs { all, file }: LockArgs) -> error::Result<()> {
+    let removed = if all {
+        agent::remove_all()?
+    } else {
+        agent::remove_key(file.get_file()?)?
+    };
+
+    if !removed {
+        println!("nothing to lock");
+    }
+
+    Ok(())
+}
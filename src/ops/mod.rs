@@ -5,12 +5,28 @@ use crate::error;
 mod add;
 mod add_gauth;
 mod add_json;
+mod add_motp;
+mod add_ocra;
 mod add_url;
+mod add_yandex;
+mod agent;
+mod check;
 mod codes;
 mod drop;
 mod edit;
+mod lock;
 mod new;
+mod pin;
+mod recovery;
 mod rename;
+mod reorder;
+mod respond;
+mod selftest;
+mod set_layout;
+mod status;
+mod tag;
+mod tui;
+mod unlock;
 mod view;
 
 #[derive(Debug, Subcommand)]
@@ -21,10 +37,27 @@ pub enum OpCmd {
     AddJson(add_json::AddJsonArgs),
     AddUrl(add_url::AddUrlArgs),
     AddGauth(add_gauth::AddGauthArgs),
+    AddOcra(add_ocra::AddOcraArgs),
+    AddMotp(add_motp::AddMotpArgs),
+    AddYandex(add_yandex::AddYandexArgs),
+    Respond(respond::RespondArgs),
     View(view::ViewArgs),
     Edit(edit::EditArgs),
     Rename(rename::RenameArgs),
     Drop(drop::DropArgs),
+    Reorder(reorder::ReorderArgs),
+    Pin(pin::PinArgs),
+    SetLayout(set_layout::SetLayoutArgs),
+    Tag(tag::TagArgs),
+    Recovery(recovery::RecoveryArgs),
+    Check(check::CheckArgs),
+    Selftest(selftest::SelftestArgs),
+    Unlock(unlock::UnlockArgs),
+    Lock(lock::LockArgs),
+    Status(status::StatusArgs),
+    Tui(tui::TuiArgs),
+    #[command(hide = true)]
+    Agent(agent::AgentArgs),
 }
 
 /// processes the first argument and then runs the desired operation
@@ -36,9 +69,25 @@ pub fn run(cmd: OpCmd) -> error::Result<()> {
         OpCmd::AddJson(args) => add_json::run(args),
         OpCmd::AddUrl(args) => add_url::run(args),
         OpCmd::AddGauth(args) => add_gauth::run(args),
+        OpCmd::AddOcra(args) => add_ocra::run(args),
+        OpCmd::AddMotp(args) => add_motp::run(args),
+        OpCmd::AddYandex(args) => add_yandex::run(args),
+        OpCmd::Respond(args) => respond::run(args),
         OpCmd::View(args) => view::run(args),
         OpCmd::Edit(args) => edit::run(args),
         OpCmd::Rename(args) => rename::run(args),
         OpCmd::Drop(args) => drop::run(args),
+        OpCmd::Reorder(args) => reorder::run(args),
+        OpCmd::Pin(args) => pin::run(args),
+        OpCmd::SetLayout(args) => set_layout::run(args),
+        OpCmd::Tag(args) => tag::run(args),
+        OpCmd::Recovery(args) => recovery::run(args),
+        OpCmd::Check(args) => check::run(args),
+        OpCmd::Selftest(args) => selftest::run(args),
+        OpCmd::Unlock(args) => unlock::run(args),
+        OpCmd::Lock(args) => lock::run(args),
+        OpCmd::Status(args) => status::run(args),
+        OpCmd::Tui(args) => tui::run(args),
+        OpCmd::Agent(args) => agent::run(args),
     }
 }
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::chacha;
@@ -20,6 +19,10 @@ pub struct NewArgs {
     /// directory to create the new file in
     #[arg(short, long)]
     directory: Option<PathBuf>,
+
+    /// how the records of the file will be encrypted
+    #[arg(short, long, default_value = "blob")]
+    layout: types::TotpLayout,
 }
 
 /// genrates a new encrpyted totp file
@@ -27,6 +30,7 @@ pub fn run(
     NewArgs {
         mut name,
         directory,
+        layout,
     }: NewArgs,
 ) -> error::Result<()> {
     let cwd = std::env::current_dir()?;
@@ -58,13 +62,14 @@ pub fn run(
             .with_message("the specified file already exists"));
     }
 
-    let secret = cli::get_input("secret")?;
+    let secret = cli::get_secret("secret")?;
     let key = chacha::make_key(secret)?;
 
     let totp_file = types::TotpFile {
         path: file_path,
         file_type: types::TotpFileType::TOTP,
-        records: HashMap::new(),
+        layout,
+        records: types::TotpRecordDict::new(),
         key: Some(key),
     };
 
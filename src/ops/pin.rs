@@ -0,0 +1,32 @@
+use crate::cli;
+use crate::error;
+
+/// pins a record so it is always listed first
+#[derive(Debug, clap::Args)]
+pub struct PinArgs {
+    /// name of the record to pin
+    #[arg(short, long)]
+    name: String,
+
+    /// unpins the record instead
+    #[arg(long)]
+    remove: bool,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(PinArgs { name, remove, file }: PinArgs) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+
+    let Some(record) = totp_file.records.get_mut(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    record.pinned = !remove;
+    record.touch();
+
+    totp_file.update_file()?;
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+
+use crate::cli;
+use crate::error;
+use crate::types;
+use crate::SecretString;
+
+/// manages the recovery codes of records
+///
+/// recovery codes are stored with the rest of the record so they are
+/// encrypted along with it
+#[derive(Debug, clap::Args)]
+pub struct RecoveryArgs {
+    #[command(subcommand)]
+    cmd: RecoveryCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum RecoveryCmd {
+    /// adds recovery codes to a record
+    Add(AddArgs),
+    /// lists the unused recovery codes of a record
+    List(ListArgs),
+    /// marks a recovery code as used
+    Use(UseArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct AddArgs {
+    /// name of the record to add codes to
+    #[arg(short, long)]
+    name: String,
+
+    /// the codes to add
+    #[arg(required_unless_present = "from")]
+    codes: Vec<String>,
+
+    /// reads codes from a file, one per line
+    #[arg(long)]
+    from: Option<PathBuf>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+#[derive(Debug, clap::Args)]
+struct ListArgs {
+    /// name of the record to list codes for
+    #[arg(short, long)]
+    name: String,
+
+    /// includes codes that have been used
+    #[arg(short, long)]
+    all: bool,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+#[derive(Debug, clap::Args)]
+struct UseArgs {
+    /// name of the record the code belongs to
+    #[arg(short, long)]
+    name: String,
+
+    /// the code to mark as used
+    #[arg(required_unless_present_any = ["index", "next"])]
+    code: Option<String>,
+
+    /// the position of the code to mark as used as shown by list
+    #[arg(short, long, conflicts_with_all = ["code", "next"])]
+    index: Option<usize>,
+
+    /// prints the first unused code and marks it as used
+    #[arg(long, conflicts_with = "code")]
+    next: bool,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+fn add(
+    AddArgs {
+        name,
+        codes,
+        from,
+        file,
+    }: AddArgs,
+) -> error::Result<()> {
+    let mut given = codes;
+
+    if let Some(from) = from {
+        let contents = SecretString::new(std::fs::read_to_string(from)?);
+
+        given.extend(contents.lines().map(str::to_owned));
+    }
+
+    let mut totp_file = file.open()?;
+
+    let Some(record) = totp_file.records.get_mut(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    let mut added = 0;
+
+    for code in given {
+        let code = SecretString::new(code.trim().to_owned());
+
+        if code.is_empty() || record.recovery.iter().any(|r| *r.code == *code) {
+            continue;
+        }
+
+        record
+            .recovery
+            .push(types::RecoveryCode { code, used: false });
+        added += 1;
+    }
+
+    record.touch();
+
+    totp_file.update_file()?;
+
+    println!("added {} recovery codes", added);
+
+    Ok(())
+}
+
+fn list(ListArgs { name, all, file }: ListArgs) -> error::Result<()> {
+    let totp_file = file.open()?;
+
+    let Some(record) = totp_file.records.get(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    let width = record.recovery.len().to_string().len();
+
+    for (index, recovery) in record.recovery.iter().enumerate() {
+        if recovery.used && !all {
+            continue;
+        }
+
+        println!(
+            "{:>width$}  {}{}",
+            index + 1,
+            &*recovery.code,
+            if recovery.used { " (used)" } else { "" },
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+fn mark_used(
+    UseArgs {
+        name,
+        code,
+        index,
+        next,
+        file,
+    }: UseArgs,
+) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+
+    let Some(record) = totp_file.records.get_mut(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    let position = if next {
+        record.recovery.iter().position(|r| !r.used)
+    } else if let Some(index) = index {
+        index.checked_sub(1).filter(|i| *i < record.recovery.len())
+    } else {
+        let code = code.unwrap_or_default();
+
+        record.recovery.iter().position(|r| &*r.code == code.trim())
+    };
+
+    let Some(position) = position else {
+        return Err(error::Error::new(error::ErrorKind::NotFound)
+            .with_message("no matching recovery code was found"));
+    };
+
+    let recovery = &mut record.recovery[position];
+
+    if recovery.used {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("recovery code has already been used"));
+    }
+
+    recovery.used = true;
+
+    if next {
+        println!("{}", &*recovery.code);
+    }
+
+    record.touch();
+
+    totp_file.update_file()?;
+
+    Ok(())
+}
+
+pub fn run(RecoveryArgs { cmd }: RecoveryArgs) -> error::Result<()> {
+    match cmd {
+        RecoveryCmd::Add(args) => add(args),
+        RecoveryCmd::List(args) => list(args),
+        RecoveryCmd::Use(args) => mark_used(args),
+    }
+}
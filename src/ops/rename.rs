@@ -1,6 +1,5 @@
 use crate::cli;
 use crate::error;
-use crate::types;
 
 /// renames a record to a new name
 #[derive(Debug, clap::Args)]
@@ -24,13 +23,20 @@ pub fn run(
         file,
     }: RenameArgs,
 ) -> error::Result<()> {
-    let mut totp_file = types::TotpFile::from_path(file.get_file()?)?;
+    let mut totp_file = file.open()?;
 
-    let Some(record) = totp_file.records.remove(&original) else {
+    if totp_file.records.contains_key(&renamed) {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message(format!("\"{}\" already exists", renamed)));
+    }
+
+    let Some((index, _, mut record)) = totp_file.records.shift_remove_full(&original) else {
         return Err(error::build::name_not_found(original));
     };
 
-    totp_file.records.insert(renamed, record);
+    record.touch();
+
+    totp_file.records.shift_insert(index, renamed, record);
     totp_file.update_file()?;
 
     Ok(())
@@ -0,0 +1,100 @@
+use crate::cli;
+use crate::error;
+
+/// moves a record to a new position in a totp file
+///
+/// the position is used when listing records with --sort custom and is the
+/// order that records are written to the file
+#[derive(Debug, clap::Args)]
+#[command(group(
+    clap::ArgGroup::new("position")
+        .required(true)
+        .args(["before", "after", "top", "bottom", "to"])
+))]
+pub struct ReorderArgs {
+    /// name of the record to move
+    #[arg(short, long)]
+    name: String,
+
+    /// moves the record in front of another record
+    #[arg(long)]
+    before: Option<String>,
+
+    /// moves the record after another record
+    #[arg(long)]
+    after: Option<String>,
+
+    /// moves the record to the start of the file
+    #[arg(long)]
+    top: bool,
+
+    /// moves the record to the end of the file
+    #[arg(long)]
+    bottom: bool,
+
+    /// moves the record to the given position, starting at 1
+    #[arg(long)]
+    to: Option<usize>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(
+    ReorderArgs {
+        name,
+        before,
+        after,
+        top,
+        bottom,
+        to,
+        file,
+    }: ReorderArgs,
+) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+    let records = &mut totp_file.records;
+
+    let Some(from) = records.get_index_of(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    let last = records.len() - 1;
+
+    let index_of = |other: String| {
+        records
+            .get_index_of(&other)
+            .ok_or_else(|| error::build::name_not_found(other))
+    };
+
+    let target = if top {
+        0
+    } else if bottom {
+        last
+    } else if let Some(to) = to {
+        to.saturating_sub(1).min(last)
+    } else if let Some(before) = before {
+        let index = index_of(before)?;
+
+        if index > from {
+            index - 1
+        } else {
+            index
+        }
+    } else if let Some(after) = after {
+        let index = index_of(after)?;
+
+        if index < from {
+            index + 1
+        } else {
+            index
+        }
+    } else {
+        from
+    };
+
+    records.move_index(from, target);
+
+    totp_file.update_file()?;
+
+    Ok(())
+}
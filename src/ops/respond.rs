@@ -0,0 +1,108 @@
+use crate::cli;
+use crate::error;
+use crate::ocra;
+use crate::otp;
+
+/// computes the response to an ocra challenge
+///
+/// the inputs required are determined by the suite of the record
+#[derive(Debug, clap::Args)]
+pub struct RespondArgs {
+    /// the name of the ocra record
+    #[arg(short, long)]
+    name: String,
+
+    /// the challenge question to respond to
+    #[arg(short = 'q', long)]
+    challenge: String,
+
+    /// the counter value for suites that require one
+    #[arg(short, long)]
+    counter: Option<u64>,
+
+    /// the pin for suites that require one
+    ///
+    /// will be hashed with the function specified by the suite
+    #[arg(long, conflicts_with = "pin_hash")]
+    pin: Option<String>,
+
+    /// the hex encoded hash of the pin for suites that require one
+    #[arg(long)]
+    pin_hash: Option<String>,
+
+    /// the hex encoded session information for suites that require one
+    #[arg(long)]
+    session: Option<String>,
+
+    /// the unix epoch in seconds to use for suites that require a time
+    ///
+    /// defaults to the current time adjusted by the clock offsets
+    #[arg(long)]
+    time: Option<u64>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+fn parse_hex(name: &str, value: &str) -> error::Result<Vec<u8>> {
+    data_encoding::HEXLOWER_PERMISSIVE
+        .decode(value.as_bytes())
+        .map_err(|_| {
+            error::Error::new(error::ErrorKind::InvalidArgument)
+                .with_message(format!("{} is not a valid hex string", name))
+        })
+}
+
+pub fn run(
+    RespondArgs {
+        name,
+        challenge,
+        counter,
+        pin,
+        pin_hash,
+        session,
+        time,
+        file,
+    }: RespondArgs,
+) -> error::Result<()> {
+    let records = file.open()?.take_records();
+
+    let Some(record) = records.get(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    let otp::Family::Ocra(suite) = &record.family else {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message(format!("\"{}\" is not an ocra record", name)));
+    };
+
+    let pin_hash = match (pin, pin_hash) {
+        (Some(pin), _) => ocra::hash_pin(suite, pin.as_bytes()),
+        (None, Some(hash)) => Some(parse_hex("pin hash", &hash)?),
+        (None, None) => None,
+    };
+
+    let session = match session {
+        Some(session) => Some(parse_hex("session", &session)?),
+        None => None,
+    };
+
+    let time = match time {
+        Some(time) => Some(time),
+        None => cli::code_time_now()
+            .ok()
+            .map(|now| record.adjusted_time(now)),
+    };
+
+    let inputs = ocra::Inputs {
+        challenge: &challenge,
+        counter,
+        pin_hash: pin_hash.as_deref(),
+        session: session.as_deref(),
+        time,
+    };
+
+    println!("{}", ocra::respond(suite, &record.secret, &inputs)?);
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use std::num::NonZeroU64;
+
+use crate::chacha;
+use crate::error;
+use crate::otp;
+use crate::types;
+
+/// the secret used by the RFC 4226 and RFC 6238 SHA1 test vectors
+const SECRET_SHA1: &[u8] = b"12345678901234567890";
+/// the secret used by the RFC 6238 SHA256 test vectors
+const SECRET_SHA256: &[u8] = b"12345678901234567890123456789012";
+/// the secret used by the RFC 6238 SHA512 test vectors
+const SECRET_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+/// RFC 4226 appendix D, counter and expected 6 digit code
+const HOTP_VECTORS: [(u64, &str); 10] = [
+    (0, "755224"),
+    (1, "287082"),
+    (2, "359152"),
+    (3, "969429"),
+    (4, "338314"),
+    (5, "254676"),
+    (6, "287922"),
+    (7, "162583"),
+    (8, "399871"),
+    (9, "520489"),
+];
+
+/// RFC 6238 appendix B, time and expected 8 digit codes for SHA1, SHA256,
+/// and SHA512
+const TOTP_VECTORS: [(u64, &str, &str, &str); 6] = [
+    (59, "94287082", "46119246", "90693936"),
+    (1111111109, "07081804", "68084774", "25091201"),
+    (1111111111, "14050471", "67062674", "99943326"),
+    (1234567890, "89005924", "91819424", "93441116"),
+    (2000000000, "69279037", "90698825", "38618901"),
+    (20000000000, "65353130", "77737706", "47863826"),
+];
+
+/// known answers for the SHA256 and SHA512 algorithms which use SHA-3
+///
+/// there are no published vectors for these so they were computed with the
+/// RFC 6238 secrets. they guard the codes of existing records from changing
+const SHA3_VECTORS: [(u64, &str, &str); 3] = [
+    (59, "03503818", "01892432"),
+    (1111111109, "00384900", "25574199"),
+    (2000000000, "49355738", "39414928"),
+];
+
+/// runs the published test vectors to confirm that codes are generated
+/// correctly
+///
+/// runs the RFC 4226 HOTP and RFC 6238 TOTP vectors along with an
+/// encrypt/decrypt round trip. the SHA3 algorithms do not have published
+/// vectors and are checked against known answers. returns an error if any
+/// check fails
+#[derive(Debug, clap::Args)]
+pub struct SelftestArgs {}
+
+/// tracks the results of the checks
+#[derive(Default)]
+struct Results {
+    passed: usize,
+    failed: usize,
+}
+
+impl Results {
+    fn check(&mut self, name: String, expected: &str, result: &str) {
+        if expected == result {
+            self.passed += 1;
+
+            println!("pass {}", name);
+        } else {
+            self.failed += 1;
+
+            println!("FAIL {} expected {} got {}", name, expected, result);
+        }
+    }
+}
+
+/// the secret and the times with expected 8 digit codes for the given
+/// algorithm
+fn totp_vectors(algo: &otp::Algo) -> (&'static [u8], Vec<(u64, &'static str)>) {
+    match algo {
+        otp::Algo::SHA1 => (
+            SECRET_SHA1,
+            TOTP_VECTORS
+                .iter()
+                .map(|(t, code, _, _)| (*t, *code))
+                .collect(),
+        ),
+        otp::Algo::SHA2_256 => (
+            SECRET_SHA256,
+            TOTP_VECTORS
+                .iter()
+                .map(|(t, _, code, _)| (*t, *code))
+                .collect(),
+        ),
+        otp::Algo::SHA2_512 => (
+            SECRET_SHA512,
+            TOTP_VECTORS
+                .iter()
+                .map(|(t, _, _, code)| (*t, *code))
+                .collect(),
+        ),
+        otp::Algo::SHA256 => (
+            SECRET_SHA256,
+            SHA3_VECTORS
+                .iter()
+                .map(|(t, code, _)| (*t, *code))
+                .collect(),
+        ),
+        otp::Algo::SHA512 => (
+            SECRET_SHA512,
+            SHA3_VECTORS
+                .iter()
+                .map(|(t, _, code)| (*t, *code))
+                .collect(),
+        ),
+    }
+}
+
+/// encrypts and decrypts a known value
+fn chacha_round_trip(results: &mut Results) -> error::Result<()> {
+    let data = b"totp-cli selftest";
+    let key = chacha::make_key("selftest")?;
+    let nonce = chacha::make_nonce()?;
+
+    let encrypted = chacha::encrypt_data(&key, &nonce, data)?;
+    let decrypted = chacha::decrypt_data(&key, &nonce, &encrypted)?;
+
+    results.check(
+        String::from("chacha round trip"),
+        &String::from_utf8_lossy(data),
+        &String::from_utf8_lossy(&decrypted),
+    );
+
+    let wrong_key = chacha::make_key("not selftest")?;
+    let rejected = if chacha::decrypt_data(&wrong_key, &nonce, &encrypted).is_err() {
+        "rejected"
+    } else {
+        "accepted"
+    };
+
+    results.check(String::from("chacha wrong key"), "rejected", rejected);
+
+    Ok(())
+}
+
+pub fn run(_args: SelftestArgs) -> error::Result<()> {
+    let mut results = Results::default();
+    let hotp_digits = types::Digits::new(6)?;
+    let totp_digits = types::Digits::new(8)?;
+    let step = NonZeroU64::new(30).unwrap();
+
+    for (counter, expected) in HOTP_VECTORS {
+        let code = otp::hotp(&otp::Algo::SHA1, SECRET_SHA1, hotp_digits, counter)?;
+
+        results.check(format!("hotp SHA1 counter {}", counter), expected, &code);
+    }
+
+    let algos = [
+        otp::Algo::SHA1,
+        otp::Algo::SHA256,
+        otp::Algo::SHA512,
+        otp::Algo::SHA2_256,
+        otp::Algo::SHA2_512,
+    ];
+
+    for algo in algos {
+        let (secret, vectors) = totp_vectors(&algo);
+
+        for (time, expected) in vectors {
+            let code = otp::totp(&algo, secret, totp_digits, step, time)?;
+
+            results.check(
+                format!("totp {} time {}", algo.as_str(), time),
+                expected,
+                &code,
+            );
+        }
+    }
+
+    chacha_round_trip(&mut results)?;
+
+    println!("\n{} passed, {} failed", results.passed, results.failed);
+
+    if results.failed > 0 {
+        return Err(error::Error::new(error::ErrorKind::IntegrityError)
+            .with_message(format!("{} checks failed", results.failed)));
+    }
+
+    Ok(())
+}
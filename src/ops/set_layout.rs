@@ -0,0 +1,28 @@
+use crate::cli;
+use crate::error;
+use crate::types;
+
+/// changes how the records of an encrypted totp file are stored
+#[derive(Debug, clap::Args)]
+pub struct SetLayoutArgs {
+    /// the new layout of the file
+    #[arg(short, long)]
+    layout: types::TotpLayout,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(SetLayoutArgs { layout, file }: SetLayoutArgs) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+
+    if totp_file.key.is_none() {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("only encrypted totp files have a layout"));
+    }
+
+    totp_file.layout = layout;
+    totp_file.update_file()?;
+
+    Ok(())
+}
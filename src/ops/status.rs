@@ -0,0 +1,24 @@
+use crate::agent;
+use crate::error;
+
+/// shows the files currently unlocked by the agent
+#[derive(Debug, clap::Args)]
+pub struct StatusArgs {}
+
+pub fn run(_args: StatusArgs) -> error::Result<()> {
+    let Some(files) = agent::status()? else {
+        println!("agent is not running");
+
+        return Ok(());
+    };
+
+    if files.is_empty() {
+        println!("no files unlocked");
+    }
+
+    for file in files {
+        println!("{}s {}", file.remaining, file.path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use clap::Subcommand;
+
+use crate::cli;
+use crate::error;
+use crate::types;
+
+/// manages the tags of records
+#[derive(Debug, clap::Args)]
+pub struct TagArgs {
+    #[command(subcommand)]
+    cmd: TagCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum TagCmd {
+    /// adds tags to a record
+    Add(ChangeArgs),
+    /// removes tags from a record
+    Remove(ChangeArgs),
+    /// lists every tag with the number of records using it
+    ///
+    /// files using the per record layout will not prompt for the secret
+    List(ListArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct ChangeArgs {
+    /// name of the record to change
+    #[arg(short, long)]
+    name: String,
+
+    /// the tags to add or remove
+    #[arg(required = true)]
+    tags: Vec<String>,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+#[derive(Debug, clap::Args)]
+struct ListArgs {
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+/// adds or removes tags from a record
+fn change(ChangeArgs { name, tags, file }: ChangeArgs, add: bool) -> error::Result<()> {
+    let mut totp_file = file.open()?;
+
+    let Some(record) = totp_file.records.get_mut(&name) else {
+        return Err(error::build::name_not_found(name));
+    };
+
+    for tag in tags {
+        if add {
+            if !record.tags.contains(&tag) {
+                record.tags.push(tag);
+            }
+        } else {
+            record.tags.retain(|t| *t != tag);
+        }
+    }
+
+    record.touch();
+
+    totp_file.update_file()?;
+
+    Ok(())
+}
+
+fn list(ListArgs { file }: ListArgs) -> error::Result<()> {
+    let path = file.get_file()?;
+
+    let meta = match types::TotpFile::read_metadata(&path)? {
+        Some(meta) => meta,
+        None => cli::open_file(&path)?.metadata(),
+    };
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for record in meta.values() {
+        for tag in &record.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    for (tag, count) in counts {
+        println!("{} ({})", tag, count);
+    }
+
+    Ok(())
+}
+
+pub fn run(TagArgs { cmd }: TagArgs) -> error::Result<()> {
+    match cmd {
+        TagCmd::Add(args) => change(args, true),
+        TagCmd::Remove(args) => change(args, false),
+        TagCmd::List(args) => list(args),
+    }
+}
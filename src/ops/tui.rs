@@ -0,0 +1,19 @@
+use crate::cli;
+use crate::error;
+use crate::tui;
+
+/// opens an interactive interface for a totp file
+///
+/// records can be searched, copied, viewed, added, edited, renamed, and
+/// dropped while codes are updated live
+#[derive(Debug, clap::Args)]
+pub struct TuiArgs {
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(TuiArgs { file }: TuiArgs) -> error::Result<()> {
+    let totp_file = file.open()?;
+
+    tui::run(totp_file)
+}
@@ -0,0 +1,34 @@
+use crate::agent;
+use crate::cli;
+use crate::error;
+
+/// unlocks an encrypted totp file for a period of time
+///
+/// the key of the file is held by a background agent so that other
+/// operations do not have to prompt for the secret until the timeout has
+/// passed or the file is locked again
+#[derive(Debug, clap::Args)]
+pub struct UnlockArgs {
+    /// the number of seconds to keep the file unlocked
+    #[arg(short, long, default_value_t = agent::DEFAULT_TIMEOUT)]
+    timeout: u64,
+
+    #[command(flatten)]
+    file: cli::RecordFile,
+}
+
+pub fn run(UnlockArgs { timeout, file }: UnlockArgs) -> error::Result<()> {
+    let path = file.get_file()?;
+    let totp_file = cli::open_file(&path)?;
+
+    let Some(key) = totp_file.key.as_ref() else {
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message("only encrypted totp files can be unlocked"));
+    };
+
+    agent::add_key(&path, key, timeout)?;
+
+    println!("unlocked for {}s", timeout);
+
+    Ok(())
+}
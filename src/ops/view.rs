@@ -1,34 +1,89 @@
 use crate::cli;
 use crate::error;
 use crate::print;
+use crate::select;
 use crate::types;
-use crate::util;
 
 /// views records of a totp file
 #[derive(Debug, clap::Args)]
 pub struct ViewArgs {
-    /// name of a specific record to view
-    #[arg(short, long)]
-    name: Option<String>,
+    #[command(flatten)]
+    select: select::RecordSelect,
+
+    #[command(flatten)]
+    filter: select::RecordFilter,
+
+    #[command(flatten)]
+    sort: select::RecordSort,
+
+    /// only prints the names of records, one per line
+    ///
+    /// files using the per record layout will not prompt for the secret
+    #[arg(long)]
+    names_only: bool,
+
+    /// the format to print records in
+    #[arg(short, long, default_value = "text", conflicts_with = "names_only")]
+    output: cli::OutputFormat,
+
+    /// includes the BASE32 secret in json or tsv output
+    #[arg(long)]
+    show_secret: bool,
 
     #[command(flatten)]
     file: cli::RecordFile,
 }
 
-pub fn run(ViewArgs { name, file }: ViewArgs) -> error::Result<()> {
-    let totp_file = types::TotpFile::from_path(file.get_file()?)?;
+pub fn run(
+    ViewArgs {
+        select,
+        filter,
+        sort,
+        names_only,
+        output,
+        show_secret,
+        file,
+    }: ViewArgs,
+) -> error::Result<()> {
+    let path = file.get_file()?;
 
-    if let Some(name) = name {
-        if let Some(record) = totp_file.records.get(&name) {
-            print::print_totp_record(&name, record);
-        } else {
-            return Err(error::build::name_not_found(name));
+    if names_only {
+        let meta = match types::TotpFile::read_metadata(&path)? {
+            Some(meta) => meta,
+            None => cli::open_file(&path)?.metadata(),
+        };
+
+        for name in select::resolve(&meta, &select, &filter, &sort)? {
+            println!("{}", name);
         }
-    } else {
-        let longest_key = util::longest_value(totp_file.records.keys(), Some(80));
 
-        print::print_records_list(&totp_file.records, &longest_key, &print::print_totp_record);
-    };
+        return Ok(());
+    }
+
+    let totp_file = cli::open_file(&path)?;
+    let names = select::resolve(&totp_file.metadata(), &select, &filter, &sort)?;
+    let records: Vec<(&String, &types::TotpRecord)> = names
+        .iter()
+        .map(|name| (name, &totp_file.records[name]))
+        .collect();
+
+    if !matches!(output, cli::OutputFormat::Text) {
+        let outputs: Vec<print::RecordOutput> = records
+            .iter()
+            .map(|(name, record)| print::RecordOutput::new(name, record, show_secret))
+            .collect();
+
+        return print::print_records_output(output, &outputs);
+    }
+
+    match records.as_slice() {
+        [(name, record)] if !select.is_all() => print::print_totp_record(name, record),
+        _ => {
+            let longest_key = print::longest_value(names.iter(), Some(80));
+
+            print::print_records_list(&records, &longest_key, &print::print_totp_record);
+        }
+    }
 
     Ok(())
 }
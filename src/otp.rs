@@ -1,19 +1,39 @@
+use md5::Digest;
 use serde::{Deserialize, Serialize};
 
+use std::num::NonZeroU64;
+
+use super::error::Result as CrateResult;
 use super::mac;
+use super::ocra;
+use super::types::Digits;
 
 /// default step for totp
 pub const _DEFAULT_STEP: u64 = 30;
 /// default digit legnth for totp
 pub const _DEFAULT_DIGITS: u32 = 8;
+/// minimum number of digits allowed for a code
+pub const MIN_DIGITS: u32 = 4;
+/// maximum number of digits allowed for a code
+///
+/// the truncated hash is 31 bits which cannot fill more than 10 digits
+pub const MAX_DIGITS: u32 = 10;
 
 /// the available algorithms for otp
-#[derive(Debug, Clone, Serialize, Deserialize, clap::ValueEnum)]
-#[value(rename_all = "UPPER")]
+///
+/// SHA256 and SHA512 use the SHA-3 functions so that existing records keep
+/// generating the same codes. SHA2_256 and SHA2_512 are the SHA-2 functions
+/// used by RFC 6238 and otpauth urls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum), value(rename_all = "UPPER"))]
 pub enum Algo {
     SHA1,
     SHA256,
     SHA512,
+    #[cfg_attr(feature = "cli", value(name = "SHA2_256"))]
+    SHA2_256,
+    #[cfg_attr(feature = "cli", value(name = "SHA2_512"))]
+    SHA2_512,
 }
 
 impl Algo {
@@ -28,6 +48,8 @@ impl Algo {
             "SHA1" => Ok(Algo::SHA1),
             "SHA256" => Ok(Algo::SHA256),
             "SHA512" => Ok(Algo::SHA512),
+            "SHA2_256" => Ok(Algo::SHA2_256),
+            "SHA2_512" => Ok(Algo::SHA2_512),
             _ => Err(()),
         }
     }
@@ -38,6 +60,8 @@ impl Algo {
             Algo::SHA1 => "SHA1",
             Algo::SHA256 => "SHA256",
             Algo::SHA512 => "SHA512",
+            Algo::SHA2_256 => "SHA2_256",
+            Algo::SHA2_512 => "SHA2_512",
         }
     }
 
@@ -69,12 +93,161 @@ impl Into<String> for Algo {
     }
 }
 
+/// step used by mobile-otp codes
+pub const MOTP_STEP: u64 = 10;
+/// number of characters in a mobile-otp code
+pub const MOTP_DIGITS: u32 = 6;
+/// number of characters in a yandex key code
+pub const YANDEX_DIGITS: u32 = 8;
+/// the characters used by yandex key codes
+const YANDEX_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// the characters used by steam guard codes
+const STEAM_ALPHABET: &[u8] = b"23456789BCDFGHJKMNPQRTVWXY";
+/// number of characters in a steam guard code
+pub const STEAM_DIGITS: u32 = 5;
+
+/// the characters used by hex codes
+const HEX_ALPHABET: &str = "0123456789abcdef";
+
+/// a set of characters used to render a code
+///
+/// guaranteed to contain at least 2 characters with no duplicates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Alphabet(String);
+
+impl Alphabet {
+    pub fn new(chars: String) -> Result<Alphabet, &'static str> {
+        let mut seen: Vec<char> = Vec::new();
+
+        for c in chars.chars() {
+            if seen.contains(&c) {
+                return Err("alphabet contains duplicate characters");
+            }
+
+            seen.push(c);
+        }
+
+        if seen.len() < 2 {
+            return Err("alphabet must contain at least 2 characters");
+        }
+
+        Ok(Alphabet(chars))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl<'de> Deserialize<'de> for Alphabet {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Alphabet::new(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// the available ways to render a code
+///
+/// decimal is the standard numeric code. steam renders the code using the
+/// characters of steam guard. hex and alphabet render the code as a number
+/// in the base of the characters available, most significant first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoder {
+    #[default]
+    Decimal,
+    Steam,
+    Hex,
+    Alphabet(Alphabet),
+}
+
+impl Encoder {
+    /// the number of digits commonly used with the encoder
+    pub fn default_digits(&self) -> u32 {
+        match self {
+            Encoder::Steam => STEAM_DIGITS,
+            _ => 6,
+        }
+    }
+}
+
+impl std::fmt::Display for Encoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoder::Decimal => f.write_str("Decimal"),
+            Encoder::Steam => f.write_str("Steam"),
+            Encoder::Hex => f.write_str("Hex"),
+            Encoder::Alphabet(chars) => write!(f, "Alphabet \"{}\"", chars.as_str()),
+        }
+    }
+}
+
+/// parses an encoder from a string
+///
+/// accepts "decimal", "steam", "hex", or "alphabet:" followed by the
+/// characters to use
+impl std::str::FromStr for Encoder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(chars) = s.strip_prefix("alphabet:") {
+            return Ok(Encoder::Alphabet(Alphabet::new(chars.to_owned())?));
+        }
+
+        match s.to_ascii_lowercase().as_str() {
+            "decimal" => Ok(Encoder::Decimal),
+            "steam" => Ok(Encoder::Steam),
+            "hex" => Ok(Encoder::Hex),
+            _ => Err(format!(
+                "unknown encoder \"{}\". expected decimal, steam, hex, or alphabet:<chars>",
+                s
+            )),
+        }
+    }
+}
+
+/// the families of algorithms a record can use
+///
+/// totp records generate codes from the current time. ocra records compute
+/// responses to challenges using the stored suite. motp and yandex records
+/// generate codes from the current time and a pin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Family {
+    #[default]
+    Totp,
+    Ocra(ocra::Suite),
+    Motp,
+    Yandex,
+}
+
+impl Family {
+    /// if the family requires a pin to generate codes
+    pub fn requires_pin(&self) -> bool {
+        matches!(self, Family::Motp | Family::Yandex)
+    }
+}
+
+impl std::fmt::Display for Family {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Family::Totp => f.write_str("TOTP"),
+            Family::Ocra(suite) => write!(f, "OCRA {}", suite),
+            Family::Motp => f.write_str("mOTP"),
+            Family::Yandex => f.write_str("Yandex"),
+        }
+    }
+}
+
 /// runs the actual mac algorithm specified
 fn one_off(algo: &Algo, secret: &[u8], data: &[u8]) -> mac::Result<Vec<u8>> {
     match algo {
         Algo::SHA1 => mac::one_off_sha1(secret, data),
-        Algo::SHA256 => mac::one_off_sha256(secret, data),
-        Algo::SHA512 => mac::one_off_sha512(secret, data),
+        Algo::SHA256 => mac::one_off_sha3_256(secret, data),
+        Algo::SHA512 => mac::one_off_sha3_512(secret, data),
+        Algo::SHA2_256 => mac::one_off_sha2_256(secret, data),
+        Algo::SHA2_512 => mac::one_off_sha2_512(secret, data),
     }
 }
 
@@ -97,46 +270,399 @@ fn pad_string(uint_string: String, digits: usize) -> String {
     }
 }
 
-/// generate integer string for otp algorithms
+/// dynamic truncation of a hash into a 31 bit value
 ///
-/// creates the integer string for the given algorithm. will pad the string
-/// if it is not long enough for the given amount of digits.
-pub fn generate_integer_string(
-    algorithm: &Algo,
-    secret: &[u8],
-    digits: u32,
-    data: &[u8],
-) -> String {
-    let hash = one_off(algorithm, secret, data).unwrap();
-
+/// uses the last 4 bits of the hash as the offset of the 4 bytes to use
+pub fn truncate(hash: &[u8]) -> u64 {
     let offset = (hash[hash.len() - 1] & 0xf) as usize;
-    let binary = ((hash[offset] & 0x7f) as u64) << 24
+
+    ((hash[offset] & 0x7f) as u64) << 24
         | (hash[offset + 1] as u64) << 16
         | (hash[offset + 2] as u64) << 8
-        | (hash[offset + 3] as u64);
+        | (hash[offset + 3] as u64)
+}
 
+/// renders the truncated value as a decimal string of the given digits
+fn decimal_string(binary: u64, digits: u32) -> String {
     let uint_string = (binary % 10u64.pow(digits)).to_string();
     let digits = digits as usize;
 
     pad_string(uint_string, digits)
 }
 
-/// create an hotp hash
-pub fn _hotp<S>(secret: S, digits: u32, counter: u64) -> String
+/// renders the truncated value using the steam guard characters
+///
+/// the least significant character is first
+fn steam_string(mut binary: u64, digits: u32) -> String {
+    let radix = STEAM_ALPHABET.len() as u64;
+    let mut rtn = String::with_capacity(digits as usize);
+
+    for _ in 0..digits {
+        rtn.push(STEAM_ALPHABET[(binary % radix) as usize] as char);
+        binary /= radix;
+    }
+
+    rtn
+}
+
+/// renders the truncated value as a number in the base of the given
+/// characters
+///
+/// the most significant character is first and will be padded with the
+/// first character of the alphabet
+fn positional_string(mut binary: u64, digits: u32, alphabet: &str) -> String {
+    let chars: Vec<char> = alphabet.chars().collect();
+    let radix = chars.len() as u64;
+    let mut rtn = Vec::with_capacity(digits as usize);
+
+    for _ in 0..digits {
+        rtn.push(chars[(binary % radix) as usize]);
+        binary /= radix;
+    }
+
+    rtn.iter().rev().collect()
+}
+
+/// splits a code into groups of the given size separated by spaces
+///
+/// groups start from the beginning of the code so the last group may be
+/// smaller than the rest
+pub fn group_code(code: &str, size: usize) -> String {
+    let mut rtn = String::with_capacity(code.len() + code.len() / size.max(1));
+
+    for (index, c) in code.chars().enumerate() {
+        if index != 0 && size != 0 && index % size == 0 {
+            rtn.push(' ');
+        }
+
+        rtn.push(c);
+    }
+
+    rtn
+}
+
+/// generate a code for otp algorithms
+///
+/// creates the code for the given algorithm. the hash is truncated the same
+/// for all encoders and then rendered to the given amount of digits. decimal
+/// codes will be padded if not long enough for the given amount of digits.
+pub fn generate_code(
+    algorithm: &Algo,
+    secret: &[u8],
+    digits: Digits,
+    encoder: &Encoder,
+    data: &[u8],
+) -> CrateResult<String> {
+    let hash = one_off(algorithm, secret, data)?;
+    let binary = truncate(&hash);
+    let digits = digits.get();
+
+    Ok(match encoder {
+        Encoder::Decimal => decimal_string(binary, digits),
+        Encoder::Steam => steam_string(binary, digits),
+        Encoder::Hex => positional_string(binary, digits, HEX_ALPHABET),
+        Encoder::Alphabet(chars) => positional_string(binary, digits, chars.as_str()),
+    })
+}
+
+/// generate a mobile-otp code
+///
+/// the code is the first characters of the md5 hex digest of the counter,
+/// the hex encoded secret, and the pin. the counter is expected to use a step
+/// of [MOTP_STEP]
+pub fn motp_code(secret: &[u8], pin: &str, digits: Digits, counter: u64) -> String {
+    let mut hasher = md5::Md5::new();
+    hasher.update(counter.to_string().as_bytes());
+    hasher.update(data_encoding::HEXLOWER.encode(secret).as_bytes());
+    hasher.update(pin.as_bytes());
+
+    let mut rtn = data_encoding::HEXLOWER.encode(&hasher.finalize());
+    rtn.truncate(digits.get() as usize);
+    rtn
+}
+
+/// generate a yandex key code
+///
+/// the hmac key is the sha256 hash of the pin and the first 16 bytes of the
+/// secret with a leading zero byte removed. the hmac is truncated to 63 bits
+/// and rendered with the lowercase latin alphabet
+pub fn yandex_code(secret: &[u8], pin: &str, digits: Digits, counter: u64) -> CrateResult<String> {
+    let secret = &secret[..secret.len().min(16)];
+
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(pin.as_bytes());
+    hasher.update(secret);
+    let key = hasher.finalize();
+    let key = if key[0] == 0 { &key[1..] } else { &key[..] };
+
+    let hash = mac::one_off_sha2_256(key, &counter.to_be_bytes())?;
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[offset..offset + 8]);
+
+    let digits = digits.get();
+    let binary = (u64::from_be_bytes(bytes) & 0x7fff_ffff_ffff_ffff) % 26u64.pow(digits);
+
+    Ok(positional_string(binary, digits, YANDEX_ALPHABET))
+}
+
+/// generates a decimal hotp code for the given counter
+pub fn hotp<S>(algorithm: &Algo, secret: S, digits: Digits, counter: u64) -> CrateResult<String>
 where
     S: AsRef<[u8]>,
 {
     let counter_bytes = counter.to_be_bytes();
 
-    generate_integer_string(&Algo::SHA1, secret.as_ref(), digits, &counter_bytes)
+    generate_code(
+        algorithm,
+        secret.as_ref(),
+        digits,
+        &Encoder::Decimal,
+        &counter_bytes,
+    )
+}
+
+/// generates a decimal totp code for the given unix epoch
+pub fn totp<S>(
+    algorithm: &Algo,
+    secret: S,
+    digits: Digits,
+    step: NonZeroU64,
+    time: u64,
+) -> CrateResult<String>
+where
+    S: AsRef<[u8]>,
+{
+    hotp(algorithm, secret, digits, time / step.get())
 }
 
-/// create an totp hash
-pub fn _totp<S>(algorithm: &Algo, secret: S, digits: u32, step: u64, time: u64) -> String
+/// verifies an hotp code
+///
+/// checks the given counter and up to window counters after it. returns the
+/// counter that matched the code
+pub fn verify_hotp<S>(
+    algorithm: &Algo,
+    secret: S,
+    digits: Digits,
+    counter: u64,
+    window: u64,
+    code: &str,
+) -> CrateResult<Option<u64>>
 where
     S: AsRef<[u8]>,
 {
-    let data = (time / step).to_be_bytes();
+    for c in counter..=counter.saturating_add(window) {
+        if hotp(algorithm, secret.as_ref(), digits, c)? == code {
+            return Ok(Some(c));
+        }
+    }
+
+    Ok(None)
+}
 
-    generate_integer_string(algorithm, secret.as_ref(), digits, &data)
+/// verifies a totp code for the given unix epoch
+///
+/// checks up to window steps before and after the given time to allow for
+/// clock drift
+pub fn verify_totp<S>(
+    algorithm: &Algo,
+    secret: S,
+    digits: Digits,
+    step: NonZeroU64,
+    time: u64,
+    window: u64,
+    code: &str,
+) -> CrateResult<bool>
+where
+    S: AsRef<[u8]>,
+{
+    let counter = time / step.get();
+
+    Ok(verify_hotp(
+        algorithm,
+        secret,
+        digits,
+        counter.saturating_sub(window),
+        counter.min(window).saturating_add(window),
+        code,
+    )?
+    .is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET_SHA1: &[u8] = b"12345678901234567890";
+    const SECRET_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SECRET_SHA512: &[u8] =
+        b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    fn digits(digits: u32) -> Digits {
+        Digits::new(digits).unwrap()
+    }
+
+    fn step() -> NonZeroU64 {
+        NonZeroU64::new(30).unwrap()
+    }
+
+    #[test]
+    fn rfc4226_hotp() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(
+                hotp(&Algo::SHA1, SECRET_SHA1, digits(6), counter as u64).unwrap(),
+                *code
+            );
+        }
+    }
+
+    #[test]
+    fn rfc6238_totp() {
+        let vectors = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+
+        for (time, sha1, sha256, sha512) in vectors {
+            assert_eq!(
+                totp(&Algo::SHA1, SECRET_SHA1, digits(8), step(), time).unwrap(),
+                sha1
+            );
+            assert_eq!(
+                totp(&Algo::SHA2_256, SECRET_SHA256, digits(8), step(), time).unwrap(),
+                sha256
+            );
+            assert_eq!(
+                totp(&Algo::SHA2_512, SECRET_SHA512, digits(8), step(), time).unwrap(),
+                sha512
+            );
+        }
+    }
+
+    #[test]
+    fn sha256_and_sha512_use_sha3() {
+        let vectors = [
+            (59, "03503818", "01892432"),
+            (1111111109, "00384900", "25574199"),
+            (2000000000, "49355738", "39414928"),
+        ];
+
+        for (time, sha256, sha512) in vectors {
+            assert_eq!(
+                totp(&Algo::SHA256, SECRET_SHA256, digits(8), step(), time).unwrap(),
+                sha256
+            );
+            assert_eq!(
+                totp(&Algo::SHA512, SECRET_SHA512, digits(8), step(), time).unwrap(),
+                sha512
+            );
+        }
+    }
+
+    #[test]
+    fn steam_guard() {
+        let secret = b"superdupersecret";
+
+        for (time, expected) in [(3000029, "94R9D"), (3000030, "YRGQJ")] {
+            let code = generate_code(
+                &Algo::SHA1,
+                secret,
+                digits(STEAM_DIGITS),
+                &Encoder::Steam,
+                &(time / 30u64).to_be_bytes(),
+            )
+            .unwrap();
+
+            assert_eq!(code, expected);
+        }
+    }
+
+    #[test]
+    fn yandex_key() {
+        let vectors = [
+            (
+                "5239",
+                "6SB2IKNM6OBZPAVBVTOHDKS4FAAAAAAADFUTQMBTRY",
+                1641559648,
+                "umozdicq",
+            ),
+            (
+                "7586",
+                "LA2V6KMCGYMWWVEW64RNP3JA3IAAAAAAHTSG4HRZPI",
+                1581064020,
+                "oactmacq",
+            ),
+            (
+                "7586",
+                "LA2V6KMCGYMWWVEW64RNP3JA3IAAAAAAHTSG4HRZPI",
+                1581090810,
+                "wemdwrix",
+            ),
+            (
+                "5210481216086702",
+                "JBGSAU4G7IEZG6OY4UAXX62JU4AAAAAAHTSG4HRZPI",
+                1581091469,
+                "dfrpywob",
+            ),
+            (
+                "5210481216086702",
+                "JBGSAU4G7IEZG6OY4UAXX62JU4AAAAAAHTSG4HRZPI",
+                1581093059,
+                "vunyprpd",
+            ),
+        ];
+
+        for (pin, secret, time, expected) in vectors {
+            let secret = data_encoding::BASE32_NOPAD
+                .decode(secret.as_bytes())
+                .unwrap();
+
+            assert_eq!(
+                yandex_code(&secret, pin, digits(YANDEX_DIGITS), time / 30).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn mobile_otp() {
+        // md5("116532442" + "e3152afee62599c8" + "1234")
+        let secret = [0xe3, 0x15, 0x2a, 0xfe, 0xe6, 0x25, 0x99, 0xc8];
+
+        assert_eq!(
+            motp_code(&secret, "1234", digits(MOTP_DIGITS), 1165324428 / MOTP_STEP),
+            "86e401"
+        );
+        assert_eq!(motp_code(&secret, "1234", digits(4), 116532442), "86e4");
+    }
+
+    #[test]
+    fn parse_encoder() {
+        assert_eq!("Steam".parse::<Encoder>().unwrap(), Encoder::Steam);
+        assert_eq!("decimal".parse::<Encoder>().unwrap(), Encoder::Decimal);
+        assert!("base64".parse::<Encoder>().is_err());
+    }
+
+    #[test]
+    fn verify_window() {
+        let code = totp(&Algo::SHA1, SECRET_SHA1, digits(8), step(), 59).unwrap();
+
+        assert!(verify_totp(&Algo::SHA1, SECRET_SHA1, digits(8), step(), 59, 0, &code).unwrap());
+        assert!(verify_totp(&Algo::SHA1, SECRET_SHA1, digits(8), step(), 89, 1, &code).unwrap());
+        assert!(!verify_totp(&Algo::SHA1, SECRET_SHA1, digits(8), step(), 89, 0, &code).unwrap());
+        assert_eq!(
+            verify_hotp(&Algo::SHA1, SECRET_SHA1, digits(6), 0, 5, "969429").unwrap(),
+            Some(3)
+        );
+    }
 }
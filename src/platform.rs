@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// a source of random bytes
+pub trait Random {
+    /// fills the given buffer with random bytes
+    fn fill(&mut self, buf: &mut [u8]) -> Result<()>;
+}
+
+/// a source of the current time
+pub trait Clock {
+    /// retrieves the current UNIX EPOCH in seconds
+    fn now(&self) -> Option<u64>;
+}
+
+/// where totp files are read from and written to
+pub trait Storage {
+    /// reads the full contents of the given path
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    /// replaces the contents of the given path
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()>;
+}
+
+/// the implementations provided by the operating system
+///
+/// uses OsRng, the system clock, and the filesystem. when targeting wasm
+/// there is no random source or clock available so random bytes will return
+/// an error and the time will be None
+#[derive(Debug, Clone, Copy, Default)]
+pub struct System;
+
+impl Random for System {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn fill(&mut self, buf: &mut [u8]) -> Result<()> {
+        use rand::TryRngCore;
+
+        rand::rngs::OsRng.try_fill_bytes(buf)?;
+
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn fill(&mut self, _buf: &mut [u8]) -> Result<()> {
+        Err(Error::new(ErrorKind::RandError).with_message("no random source is available"))
+    }
+}
+
+impl Clock for System {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> Option<u64> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|dur| dur.as_secs())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Storage for System {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(std::fs::read(path)?)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        std::fs::write(path, data).map_err(|err| {
+            Error::new(ErrorKind::IoError)
+                .with_message(format!("failed to write {}", path.display()))
+                .with_error(err)
+        })
+    }
+}
+
+/// a clock that always returns the same time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Option<u64> {
+        Some(self.0)
+    }
+}
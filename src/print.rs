@@ -1,20 +1,43 @@
+use std::collections::BTreeMap;
 use std::time::Instant;
 
+use serde::Serialize;
+
+use crate::cli::{self, OutputFormat};
+use crate::error;
 use crate::otp;
-use crate::types::{TotpRecord, TotpRecordDict};
-use crate::util;
+use crate::types::TotpRecord;
 
 /// prints the gnerated code of a [TotpRecord]
 pub fn print_totp_code(_key: &String, record: &TotpRecord) -> () {
-    let now = util::unix_epoch_sec_now().unwrap();
-    let data = (now / record.step).to_be_bytes();
+    if let otp::Family::Ocra(_) = record.family {
+        println!("challenge-response record, use respond to compute a response");
+        return;
+    }
+
+    let now = match cli::code_time_now() {
+        Ok(now) => now,
+        Err(err) => {
+            println!("{}", err.message.unwrap_or_else(|| err.kind.to_string()));
+            return;
+        }
+    };
+    let (_, time_left) = record.counter_at(now);
 
     let perf_start = Instant::now();
-    let code = otp::generate_integer_string(&record.algo, &record.secret, record.digits, &data);
+    let code = match record.code_at(now) {
+        Ok(code) => code,
+        Err(err) => {
+            println!("{}", err.message.unwrap_or_else(|| err.kind.to_string()));
+            return;
+        }
+    };
+    let code = match record.group {
+        Some(size) => otp::group_code(&code, size.get()),
+        None => code,
+    };
     let perf_end = Instant::now();
 
-    let time_left = record.step - (now % record.step);
-
     println!(
         "{}\nseconds left: {}s\n    finished: {:#?}",
         code,
@@ -29,17 +52,47 @@ pub fn print_totp_record(_key: &String, record: &TotpRecord) -> () {
     println!("base32: {}", b32);
     print!(" bytes:");
 
-    for byte in &record.secret {
+    for byte in record.secret.iter() {
         print!(" {:02X}", byte);
     }
 
-    println!(
-        " ({})\ndigits: {}\n  step: {}s\n  algo: {}",
-        record.secret.len(),
-        record.digits,
-        record.step,
-        record.algo.as_str()
-    );
+    println!(" ({})", record.secret.len());
+
+    if let otp::Family::Ocra(suite) = &record.family {
+        println!(" suite: {}", suite);
+    } else if record.family.requires_pin() {
+        println!(
+            "family: {}\ndigits: {}\n  step: {}s\n   pin: {}",
+            record.family,
+            record.digits,
+            record.step,
+            if record.pin.is_some() {
+                "stored"
+            } else {
+                "prompt"
+            }
+        );
+    } else {
+        println!(
+            "digits: {}\n  step: {}s\n  algo: {}\nencode: {}",
+            record.digits,
+            record.step,
+            record.algo.as_str(),
+            record.encoder
+        );
+
+        if let Some(group) = record.group {
+            println!(" group: {}", group);
+        }
+
+        if record.t0 != 0 {
+            println!("    t0: {}", record.t0);
+        }
+    }
+
+    if record.time_offset != 0 {
+        println!("offset: {}s", record.time_offset);
+    }
 
     if let Some(issuer) = record.issuer.as_ref() {
         println!("  issuer: {}", issuer);
@@ -48,25 +101,653 @@ pub fn print_totp_record(_key: &String, record: &TotpRecord) -> () {
     if let Some(username) = record.username.as_ref() {
         println!("username: {}", username);
     }
+
+    if let Some(folder) = record.folder.as_ref() {
+        println!("  folder: {}", folder);
+    }
+
+    if !record.tags.is_empty() {
+        println!("    tags: {}", record.tags.join(", "));
+    }
+
+    if let Some(url) = record.url.as_ref() {
+        println!("     url: {}", url);
+    }
+
+    for (key, value) in &record.fields {
+        println!("{:>8}: {}", key, value);
+    }
+
+    if !record.recovery.is_empty() {
+        let unused = record.recovery.iter().filter(|r| !r.used).count();
+
+        println!("recovery: {} of {} unused", unused, record.recovery.len());
+    }
+
+    if record.pinned {
+        println!("  pinned: yes");
+    }
+
+    if let Some(created) = record.created_at {
+        println!(" created: {}", format_timestamp(created));
+    }
+
+    if let Some(updated) = record.updated_at {
+        println!(" updated: {}", format_timestamp(updated));
+    }
+
+    if let Some(used) = record.last_used_at {
+        println!("    used: {}", format_timestamp(used));
+    }
+
+    if let Some(notes) = record.notes.as_ref() {
+        println!("   notes:");
+
+        for line in notes.lines() {
+            println!("    {}", line);
+        }
+    }
 }
 
 /// prints a list of records with their key and desired print function
 pub fn print_records_list(
-    totp_dict: &TotpRecordDict,
+    records: &[(&String, &TotpRecord)],
     longest_key: &usize,
     cb: &dyn Fn(&String, &TotpRecord) -> (),
 ) -> () {
     let mut first = true;
 
-    for (key, record) in totp_dict.iter() {
+    for (key, record) in records {
         if first {
             first = false;
         } else {
             print!("\n");
         }
 
-        println!("{}", util::pad_key(key, longest_key));
+        println!("{}", pad_key(key, longest_key));
 
         cb(key, record);
     }
 }
+
+/// formats a unix epoch as a UTC date and time
+///
+/// uses the days from civil algorithm to avoid pulling in a date library
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// counts total number of UTF-8 characters in a string
+pub fn total_chars(string: &String) -> usize {
+    let mut total = 0;
+
+    for _ in string.chars() {
+        total += 1;
+    }
+
+    total
+}
+
+/// attempts to find the longest string in an iterator
+///
+/// can optionally specify a starting point or default to 0
+pub fn longest_value<'a>(iter: impl Iterator<Item = &'a String>, starting: Option<usize>) -> usize {
+    let mut longest_key = starting.unwrap_or(0);
+
+    for key in iter {
+        let total_chars = total_chars(key);
+
+        if longest_key < total_chars {
+            longest_key = total_chars;
+        }
+    }
+
+    longest_key
+}
+
+/// pads the given key to a desired length
+///
+/// format is "{key} {padding}" with a padding character of '-'
+pub fn pad_key<K>(key: K, len: &usize) -> String
+where
+    K: AsRef<str>,
+{
+    let key_ref = key.as_ref();
+    let mut to_append = len - key_ref.len();
+    let mut rtn = String::with_capacity(to_append);
+    rtn.push_str(key_ref);
+
+    if to_append > 0 {
+        rtn.push(' ');
+        to_append -= 1;
+    }
+
+    for _ in 0..to_append {
+        rtn.push('-');
+    }
+
+    rtn
+}
+
+/// renders the remaining time of a period as a bar of the given width
+pub fn progress_bar(left: u64, step: u64, width: usize) -> String {
+    let filled = ((left as usize) * width).div_ceil(step.max(1) as usize);
+    let filled = filled.min(width);
+
+    let mut rtn = String::with_capacity(width * 3);
+
+    for index in 0..width {
+        rtn.push(if index < filled { '█' } else { '░' });
+    }
+
+    rtn
+}
+
+/// the number of columns of the terminal
+///
+/// falls back to the COLUMNS environment variable and then 80 if stdout is
+/// not a terminal
+pub fn terminal_width() -> usize {
+    ratatui::crossterm::terminal::size()
+        .ok()
+        .map(|(width, _)| width as usize)
+        .filter(|width| *width > 0)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(80)
+}
+
+/// pads or truncates a value to exactly the given number of characters
+///
+/// truncated values end with an ellipsis
+fn fit(value: &str, width: usize) -> String {
+    let total = value.chars().count();
+
+    if total <= width {
+        format!("{}{}", value, " ".repeat(width - total))
+    } else if width == 0 {
+        String::new()
+    } else {
+        let mut rtn: String = value.chars().take(width - 1).collect();
+        rtn.push('…');
+        rtn
+    }
+}
+
+/// number of characters used by the progress bar of the codes table
+const TABLE_BAR_WIDTH: usize = 10;
+
+/// renders generated codes as a table that fits in the given width
+///
+/// records that share the same step are grouped under a header with
+/// challenge-response records listed last in their own group. the name
+/// and issuer columns are truncated to fit and the issuer column is dropped
+/// if there is not enough room for it
+pub fn codes_table(records: &[(&String, &TotpRecord)], now: u64, width: usize) -> Vec<String> {
+    let mut groups: BTreeMap<Option<u64>, Vec<(&String, &TotpRecord, String)>> = BTreeMap::new();
+
+    for (name, record) in records {
+        let code = if let otp::Family::Ocra(_) = record.family {
+            String::from("respond")
+        } else {
+            match record.code_at(now) {
+                Ok(code) => match record.group {
+                    Some(size) => otp::group_code(&code, size.get()),
+                    None => code,
+                },
+                Err(err) => err.message.unwrap_or_else(|| err.kind.to_string()),
+            }
+        };
+
+        let step = match record.family {
+            otp::Family::Ocra(_) => None,
+            _ => Some(record.step.get()),
+        };
+
+        groups.entry(step).or_default().push((name, record, code));
+    }
+
+    let code_width = groups
+        .values()
+        .flatten()
+        .map(|(_, _, code)| code.chars().count())
+        .max()
+        .unwrap_or(0);
+    let left_width = groups
+        .keys()
+        .flatten()
+        .last()
+        .map(|step| step.to_string().len())
+        .unwrap_or(2)
+        + 1;
+    let name_max = records
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let issuer_max = records
+        .iter()
+        .filter_map(|(_, record)| record.issuer.as_deref())
+        .map(|issuer| issuer.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let fixed = 2 + code_width + 2 + TABLE_BAR_WIDTH + 1 + left_width;
+    let available = width.saturating_sub(fixed).max(1);
+
+    let (name_width, issuer_width) = if issuer_max == 0 {
+        (name_max.min(available), 0)
+    } else if name_max + 2 + issuer_max <= available {
+        (name_max, issuer_max)
+    } else {
+        let issuer_width = issuer_max.min(available / 3);
+
+        if issuer_width < 6 {
+            (name_max.min(available), 0)
+        } else {
+            (available - issuer_width - 2, issuer_width)
+        }
+    };
+
+    let mut lines = Vec::new();
+
+    let (ocra, timed): (Vec<_>, Vec<_>) = groups.into_iter().partition(|(step, _)| step.is_none());
+
+    for (step, group) in timed.into_iter().chain(ocra) {
+        let header = match step {
+            Some(step) => format!("── {}s ", step),
+            None => String::from("── challenge-response "),
+        };
+        let fill = width.saturating_sub(header.chars().count());
+
+        lines.push(format!("{}{}", header, "─".repeat(fill)));
+
+        for (name, record, code) in group {
+            let (_, left) = record.counter_at(now);
+            let mut line = fit(name, name_width);
+
+            if issuer_width > 0 {
+                line.push_str("  ");
+                line.push_str(&fit(
+                    record.issuer.as_deref().unwrap_or_default(),
+                    issuer_width,
+                ));
+            }
+
+            line.push_str("  ");
+            line.push_str(&fit(&code, code_width));
+            line.push_str("  ");
+
+            if let Some(step) = step {
+                line.push_str(&progress_bar(left, step, TABLE_BAR_WIDTH));
+                line.push_str(&format!(" {:>w$}", format!("{}s", left), w = left_width));
+            }
+
+            lines.push(line.trim_end().to_owned());
+        }
+    }
+
+    lines
+}
+
+/// the machine readable form of a generated code
+///
+/// code, seconds_remaining, period_start, and period_end are null if a code
+/// could not be generated. the secret is only included when requested
+#[derive(Serialize)]
+pub struct CodeOutput<'a> {
+    pub name: &'a str,
+    pub issuer: Option<&'a str>,
+    pub username: Option<&'a str>,
+    pub code: Option<String>,
+    pub seconds_remaining: Option<u64>,
+    pub period_start: Option<u64>,
+    pub period_end: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl<'a> CodeOutput<'a> {
+    const COLUMNS: [&'static str; 8] = [
+        "name",
+        "issuer",
+        "username",
+        "code",
+        "seconds_remaining",
+        "period_start",
+        "period_end",
+        "secret",
+    ];
+
+    /// creates the output of a record for the given unix epoch
+    pub fn new(name: &'a str, record: &'a TotpRecord, now: u64, show_secret: bool) -> Self {
+        let mut rtn = CodeOutput {
+            name,
+            issuer: record.issuer.as_deref(),
+            username: record.username.as_deref(),
+            code: None,
+            seconds_remaining: None,
+            period_start: None,
+            period_end: None,
+            secret: show_secret.then(|| data_encoding::BASE32.encode(&record.secret)),
+        };
+
+        if let Ok(code) = record.code_at(now) {
+            let (_, left) = record.counter_at(now);
+            let end = now + left;
+
+            rtn.code = Some(code);
+            rtn.seconds_remaining = Some(left);
+            rtn.period_start = Some(end - record.step.get());
+            rtn.period_end = Some(end);
+        }
+
+        rtn
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            opt_field(self.issuer),
+            opt_field(self.username),
+            opt_field(self.code.as_ref()),
+            opt_field(self.seconds_remaining),
+            opt_field(self.period_start),
+            opt_field(self.period_end),
+            opt_field(self.secret.as_ref()),
+        ]
+    }
+}
+
+/// the machine readable form of a record
+///
+/// the secret is only included when requested
+#[derive(Serialize)]
+pub struct RecordOutput<'a> {
+    pub name: &'a str,
+    pub issuer: Option<&'a str>,
+    pub username: Option<&'a str>,
+    pub family: String,
+    pub algo: &'a str,
+    pub digits: u32,
+    pub step: u64,
+    pub encoder: String,
+    pub t0: u64,
+    pub time_offset: i64,
+    pub tags: &'a [String],
+    pub folder: Option<&'a str>,
+    pub notes: Option<&'a str>,
+    pub url: Option<&'a str>,
+    pub fields: &'a BTreeMap<String, String>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl<'a> RecordOutput<'a> {
+    const COLUMNS: [&'static str; 20] = [
+        "name",
+        "issuer",
+        "username",
+        "family",
+        "algo",
+        "digits",
+        "step",
+        "encoder",
+        "t0",
+        "time_offset",
+        "tags",
+        "folder",
+        "notes",
+        "url",
+        "fields",
+        "created_at",
+        "updated_at",
+        "last_used_at",
+        "pinned",
+        "secret",
+    ];
+
+    pub fn new(name: &'a str, record: &'a TotpRecord, show_secret: bool) -> Self {
+        RecordOutput {
+            name,
+            issuer: record.issuer.as_deref(),
+            username: record.username.as_deref(),
+            family: record.family.to_string(),
+            algo: record.algo.as_str(),
+            digits: record.digits.get(),
+            step: record.step.get(),
+            encoder: record.encoder.to_string(),
+            t0: record.t0,
+            time_offset: record.time_offset,
+            tags: &record.tags,
+            folder: record.folder.as_deref(),
+            notes: record.notes.as_deref(),
+            url: record.url.as_deref(),
+            fields: &record.fields,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            last_used_at: record.last_used_at,
+            pinned: record.pinned,
+            secret: show_secret.then(|| data_encoding::BASE32.encode(&record.secret)),
+        }
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.to_owned(),
+            opt_field(self.issuer),
+            opt_field(self.username),
+            self.family.clone(),
+            self.algo.to_owned(),
+            self.digits.to_string(),
+            self.step.to_string(),
+            self.encoder.clone(),
+            self.t0.to_string(),
+            self.time_offset.to_string(),
+            self.tags.join(","),
+            opt_field(self.folder),
+            opt_field(self.notes),
+            opt_field(self.url),
+            self.fields
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<String>>()
+                .join(","),
+            opt_field(self.created_at),
+            opt_field(self.updated_at),
+            opt_field(self.last_used_at),
+            self.pinned.to_string(),
+            opt_field(self.secret.as_ref()),
+        ]
+    }
+}
+
+/// renders an optional value for tsv output, None is an empty field
+fn opt_field<T>(value: Option<T>) -> String
+where
+    T: std::fmt::Display,
+{
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// prints a tsv row, tabs and newlines in values are replaced with spaces
+fn print_tsv_row<S>(fields: &[S])
+where
+    S: AsRef<str>,
+{
+    let escaped: Vec<String> = fields
+        .iter()
+        .map(|f| f.as_ref().replace(['\t', '\n', '\r'], " "))
+        .collect();
+
+    println!("{}", escaped.join("\t"));
+}
+
+/// prints generated codes in the given machine readable format
+pub fn print_codes_output(format: OutputFormat, outputs: &[CodeOutput<'_>]) -> error::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(outputs)?),
+        OutputFormat::Tsv | OutputFormat::Text => {
+            print_tsv_row(&CodeOutput::COLUMNS);
+
+            for output in outputs {
+                print_tsv_row(&output.row());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// prints records in the given machine readable format
+pub fn print_records_output(
+    format: OutputFormat,
+    outputs: &[RecordOutput<'_>],
+) -> error::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(outputs)?),
+        OutputFormat::Tsv | OutputFormat::Text => {
+            print_tsv_row(&RecordOutput::COLUMNS);
+
+            for output in outputs {
+                print_tsv_row(&output.row());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use super::*;
+    use crate::types::Digits;
+    use crate::SecretBytes;
+
+    fn record(issuer: Option<&str>, step: u64) -> TotpRecord {
+        let mut record = TotpRecord::new(
+            SecretBytes::new(b"12345678901234567890".to_vec()),
+            otp::Algo::SHA1,
+            Digits::new(6).unwrap(),
+            NonZeroU64::new(step).unwrap(),
+        )
+        .unwrap();
+
+        record.issuer = issuer.map(str::to_owned);
+        record
+    }
+
+    fn width(line: &str) -> usize {
+        line.chars().count()
+    }
+
+    #[test]
+    fn codes_table_groups() {
+        let (a, b, c, d) = (
+            String::from("a"),
+            String::from("b"),
+            String::from("c"),
+            String::from("d"),
+        );
+        let mut ocra = record(None, 30);
+        ocra.family = otp::Family::Ocra("OCRA-1:HOTP-SHA1-6:QN08".parse().unwrap());
+        let (rec_a, rec_b, rec_d) = (
+            record(Some("GitHub"), 30),
+            record(None, 60),
+            record(None, 30),
+        );
+
+        let lines = codes_table(
+            &[(&c, &ocra), (&b, &rec_b), (&a, &rec_a), (&d, &rec_d)],
+            59,
+            80,
+        );
+
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("── 30s ─"));
+        assert!(lines[1].starts_with("a  GitHub  287082"));
+        assert!(lines[1].ends_with(" 1s"));
+        assert!(lines[2].starts_with("d          287082"));
+        assert!(lines[3].starts_with("── 60s ─"));
+        assert!(lines[4].starts_with("b  "));
+        assert!(lines[5].starts_with("── challenge-response ─"));
+        assert_eq!(lines[6], "c          respond");
+
+        for line in &lines {
+            assert!(width(line) <= 80, "{}", line);
+        }
+
+        assert_eq!(width(&lines[0]), 80);
+    }
+
+    #[test]
+    fn codes_table_truncates() {
+        let name = String::from("a-very-long-record-name");
+        let rec = record(Some("Example Issuer"), 30);
+
+        let lines = codes_table(&[(&name, &rec)], 59, 60);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("a-very-long-record-na…  Example Iss…  287082"));
+        assert_eq!(width(&lines[1]), 60);
+    }
+
+    #[test]
+    fn codes_table_drops_issuer() {
+        let name = String::from("a-very-long-record-name");
+        let rec = record(Some("Example Issuer"), 30);
+
+        let lines = codes_table(&[(&name, &rec)], 59, 40);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("a-very-long-rec…  287082"));
+        assert!(!lines[1].contains("Exam"));
+        assert_eq!(width(&lines[1]), 40);
+
+        let lines = codes_table(&[(&name, &rec)], 59, 120);
+
+        assert!(lines[1].starts_with("a-very-long-record-name  Example Issuer  287082"));
+    }
+
+    #[test]
+    fn format_timestamp_known() {
+        let known = [
+            (0, "1970-01-01 00:00:00 UTC"),
+            (951782400, "2000-02-29 00:00:00 UTC"),
+            (951868799, "2000-02-29 23:59:59 UTC"),
+            (4107456000, "2100-02-28 00:00:00 UTC"),
+            (4107542400, "2100-03-01 00:00:00 UTC"),
+            (253402300799, "9999-12-31 23:59:59 UTC"),
+        ];
+
+        for (secs, expected) in known {
+            assert_eq!(format_timestamp(secs), expected, "{}", secs);
+        }
+    }
+}
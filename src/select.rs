@@ -0,0 +1,476 @@
+use std::io::IsTerminal;
+
+use crate::cli;
+use crate::error;
+use crate::types;
+
+/// selects records from a file by name
+///
+/// only one of the options can be given. if none are given then every record
+/// is selected
+#[derive(Debug, clap::Args)]
+#[group(id = "select", multiple = false)]
+pub struct RecordSelect {
+    /// fuzzy matches record names
+    ///
+    /// if several records match then a list is shown to choose from
+    query: Option<String>,
+
+    /// the exact name of a record
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// selects every record with a name matching a glob pattern
+    #[arg(short, long)]
+    glob: Option<glob::Pattern>,
+
+    /// selects every record with a name matching a regular expression
+    #[arg(long)]
+    regex: Option<regex::Regex>,
+}
+
+impl RecordSelect {
+    /// true if no options were given and every record will be selected
+    pub fn is_all(&self) -> bool {
+        self.query.is_none() && self.name.is_none() && self.glob.is_none() && self.regex.is_none()
+    }
+
+    /// true if a record was given by its exact name
+    pub fn is_exact(&self) -> bool {
+        self.name.is_some()
+    }
+
+    /// the names selected from the given record names
+    ///
+    /// names are sorted and at least one will always be returned unless the
+    /// file has no records and none were requested
+    pub fn select<'a, I>(&self, names: I) -> error::Result<Vec<String>>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let all: Vec<&String> = names.into_iter().collect();
+
+        if let Some(name) = &self.name {
+            return if all.contains(&name) {
+                Ok(vec![name.clone()])
+            } else {
+                Err(error::build::name_not_found(name.clone()))
+            };
+        }
+
+        if let Some(query) = &self.query {
+            return fuzzy_select(query, &all);
+        }
+
+        let mut names: Vec<String> = all
+            .into_iter()
+            .filter(|name| match (&self.glob, &self.regex) {
+                (Some(glob), _) => glob.matches(name),
+                (None, Some(regex)) => regex.is_match(name),
+                (None, None) => true,
+            })
+            .cloned()
+            .collect();
+
+        names.sort();
+
+        if names.is_empty() && !self.is_all() {
+            return Err(error::Error::new(error::ErrorKind::NotFound)
+                .with_message("no records matched the given pattern"));
+        }
+
+        Ok(names)
+    }
+}
+
+/// filters records by their tags, folder, and timestamps
+#[derive(Debug, clap::Args)]
+pub struct RecordFilter {
+    /// only includes records with the given tag
+    ///
+    /// can be repeated to require several tags
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// only includes records in the given folder or its subfolders
+    #[arg(long)]
+    folder: Option<String>,
+
+    /// only includes records that have not been used in the given number of
+    /// days
+    ///
+    /// records that have never been used are included
+    #[arg(long, value_name = "DAYS")]
+    unused_for: Option<u64>,
+
+    /// only includes records created more than the given number of days ago
+    ///
+    /// records without a created time are included
+    #[arg(long, value_name = "DAYS")]
+    older_than: Option<u64>,
+}
+
+/// checks if a timestamp is missing or before the given number of days ago
+fn before_days(time: Option<u64>, days: u64, now: u64) -> bool {
+    match time {
+        Some(time) => time < now.saturating_sub(days.saturating_mul(86400)),
+        None => true,
+    }
+}
+
+impl RecordFilter {
+    /// checks if the metadata of a record passes the filter
+    pub fn matches(&self, meta: &types::RecordMeta) -> error::Result<bool> {
+        if !self.tags.iter().all(|tag| meta.tags.contains(tag)) {
+            return Ok(false);
+        }
+
+        if let Some(parent) = self.folder.as_deref().and_then(types::normalize_folder) {
+            if !meta
+                .folder
+                .as_deref()
+                .is_some_and(|folder| types::in_folder(folder, &parent))
+            {
+                return Ok(false);
+            }
+        }
+
+        if self.unused_for.is_none() && self.older_than.is_none() {
+            return Ok(true);
+        }
+
+        let now = cli::unix_epoch_sec_now()?;
+
+        if let Some(days) = self.unused_for {
+            if !before_days(meta.last_used_at, days, now) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(days) = self.older_than {
+            if !before_days(meta.created_at, days, now) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// what records are sorted by
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum SortKey {
+    /// the name of the record
+    #[default]
+    Name,
+    /// the issuer of the record, records without an issuer are last
+    Issuer,
+    /// when the record was created, oldest first
+    Created,
+    /// when the record was last changed, oldest first
+    Updated,
+    /// when a code was last used, least recent first
+    Used,
+    /// the order of the records in the file, see the reorder operation
+    Custom,
+}
+
+/// the order that records are listed in
+#[derive(Debug, clap::Args)]
+pub struct RecordSort {
+    /// what to sort records by
+    #[arg(long, env = "TOTP_CLI_SORT", default_value = "name")]
+    sort: SortKey,
+
+    /// reverses the sort order
+    #[arg(long)]
+    reverse: bool,
+}
+
+impl RecordSort {
+    /// sorts names by the metadata of the records
+    ///
+    /// pinned records are always listed first. records without a timestamp
+    /// are sorted first and ties are sorted by name
+    pub fn sort(&self, names: &mut [String], meta: &types::RecordMetaDict) {
+        names.sort_by(|a, b| {
+            let (meta_a, meta_b) = (&meta[a], &meta[b]);
+
+            let ordering = match self.sort {
+                SortKey::Name => std::cmp::Ordering::Equal,
+                SortKey::Issuer => {
+                    let issuer = |meta: &types::RecordMeta| {
+                        (
+                            meta.issuer.is_none(),
+                            meta.issuer.as_deref().map(str::to_lowercase),
+                        )
+                    };
+
+                    issuer(meta_a).cmp(&issuer(meta_b))
+                }
+                SortKey::Created => meta_a.created_at.cmp(&meta_b.created_at),
+                SortKey::Updated => meta_a.updated_at.cmp(&meta_b.updated_at),
+                SortKey::Used => meta_a.last_used_at.cmp(&meta_b.last_used_at),
+                SortKey::Custom => meta.get_index_of(a).cmp(&meta.get_index_of(b)),
+            }
+            .then_with(|| a.cmp(b));
+
+            let ordering = if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+
+            meta_b.pinned.cmp(&meta_a.pinned).then(ordering)
+        });
+    }
+}
+
+/// the names of the records that pass the filter and selection in order
+pub fn resolve(
+    meta: &types::RecordMetaDict,
+    select: &RecordSelect,
+    filter: &RecordFilter,
+    sort: &RecordSort,
+) -> error::Result<Vec<String>> {
+    let mut passed = Vec::new();
+
+    for (name, meta) in meta {
+        if filter.matches(meta)? {
+            passed.push(name);
+        }
+    }
+
+    let mut names = select.select(passed)?;
+
+    sort.sort(&mut names, meta);
+
+    Ok(names)
+}
+
+/// scores how well a query fuzzy matches a name
+///
+/// every character of the query must appear in the name in the same order.
+/// consecutive characters and characters at the start of a word score
+/// higher. returns None if the name does not match
+fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut index = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.to_lowercase().chars() {
+        let found = name[index..].iter().position(|c| *c == q)? + index;
+
+        score += 1;
+
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+
+        match previous {
+            Some(prev) if prev + 1 == found => score += 5,
+            Some(prev) => score -= (found - prev - 1).min(5) as i64,
+            None => score -= found.min(5) as i64,
+        }
+
+        previous = Some(found);
+        index = found + 1;
+    }
+
+    Some(score - (name.len() as i64 - index as i64).min(10) / 2)
+}
+
+/// finds the record that best matches the query
+///
+/// an exact name is always preferred. if more than one record matches then
+/// the user is asked to choose one
+fn fuzzy_select(query: &str, names: &[&String]) -> error::Result<Vec<String>> {
+    if names.iter().any(|name| *name == query) {
+        return Ok(vec![query.to_owned()]);
+    }
+
+    let mut matches: Vec<(i64, &String)> = names
+        .iter()
+        .filter_map(|name| fuzzy_score(query, name).map(|score| (score, *name)))
+        .collect();
+
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+    match matches.len() {
+        0 => Err(error::build::name_not_found(query.to_owned())),
+        1 => Ok(vec![matches[0].1.clone()]),
+        _ => {
+            let names: Vec<&String> = matches.into_iter().map(|(_, name)| name).collect();
+
+            choose(query, &names).map(|name| vec![name])
+        }
+    }
+}
+
+/// asks the user to choose one of the given names
+///
+/// fails if stdin is not a terminal since nobody would be able to answer
+fn choose(query: &str, names: &[&String]) -> error::Result<String> {
+    if !std::io::stdin().is_terminal() {
+        let list = names
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ");
+
+        return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+            .with_message(format!("\"{}\" matches several records: {}", query, list)));
+    }
+
+    for (index, name) in names.iter().enumerate() {
+        eprintln!("{:>3}) {}", index + 1, name);
+    }
+
+    loop {
+        let input = cli::get_input(format!("record [1-{}]", names.len()))?;
+        let input = input.trim();
+
+        if input.is_empty() {
+            return Err(error::Error::new(error::ErrorKind::InvalidArgument)
+                .with_message("no record was chosen"));
+        }
+
+        match input.parse::<usize>() {
+            Ok(index) if index >= 1 && index <= names.len() => {
+                return Ok(names[index - 1].clone());
+            }
+            _ => eprintln!("invalid choice"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn select(
+        query: Option<&str>,
+        name: Option<&str>,
+        glob: Option<&str>,
+        regex: Option<&str>,
+    ) -> RecordSelect {
+        RecordSelect {
+            query: query.map(str::to_owned),
+            name: name.map(str::to_owned),
+            glob: glob.map(|glob| glob::Pattern::new(glob).unwrap()),
+            regex: regex.map(|regex| regex::Regex::new(regex).unwrap()),
+        }
+    }
+
+    #[test]
+    fn before_days_cutoff() {
+        let now = 10 * 86400;
+
+        assert!(before_days(None, 1, now));
+        assert!(before_days(Some(0), 1, now));
+        assert!(before_days(Some(now - 86400 - 1), 1, now));
+        assert!(!before_days(Some(now - 86400), 1, now));
+        assert!(!before_days(Some(now), 0, now));
+        assert!(!before_days(Some(0), u64::MAX, now));
+    }
+
+    #[test]
+    fn fuzzy_ranking() {
+        let github = fuzzy_score("gh", "github-work").unwrap();
+        let graphql = fuzzy_score("gh", "graphql-hub").unwrap();
+
+        assert!(github > graphql);
+        assert_eq!(fuzzy_score("gh", "bank"), None);
+        assert_eq!(fuzzy_score("hg", "github-work"), None);
+        assert!(fuzzy_score("GH", "github-work").is_some());
+    }
+
+    #[test]
+    fn fuzzy_select_single() {
+        let list = names(&["github-work", "bank", "email"]);
+
+        let selected = select(Some("gh"), None, None, None).select(&list).unwrap();
+
+        assert_eq!(selected, names(&["github-work"]));
+    }
+
+    #[test]
+    fn fuzzy_select_exact() {
+        let list = names(&["github-work", "git", "github"]);
+
+        let selected = select(Some("git"), None, None, None).select(&list).unwrap();
+
+        assert_eq!(selected, names(&["git"]));
+    }
+
+    #[test]
+    fn select_name() {
+        let list = names(&["github", "git"]);
+
+        let selected = select(None, Some("git"), None, None).select(&list).unwrap();
+
+        assert_eq!(selected, names(&["git"]));
+
+        let err = select(None, Some("gi"), None, None)
+            .select(&list)
+            .unwrap_err();
+
+        assert!(matches!(err.kind, error::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn select_glob() {
+        let list = names(&["bank-b", "email", "bank-a"]);
+
+        let selected = select(None, None, Some("bank-*"), None)
+            .select(&list)
+            .unwrap();
+
+        assert_eq!(selected, names(&["bank-a", "bank-b"]));
+    }
+
+    #[test]
+    fn select_regex() {
+        let list = names(&["work-1", "home", "work-22"]);
+
+        let selected = select(None, None, None, Some("^work-\\d+$"))
+            .select(&list)
+            .unwrap();
+
+        assert_eq!(selected, names(&["work-1", "work-22"]));
+    }
+
+    #[test]
+    fn select_all() {
+        let list = names(&["b", "a"]);
+
+        assert_eq!(
+            select(None, None, None, None).select(&list).unwrap(),
+            names(&["a", "b"])
+        );
+        assert!(select(None, None, None, None)
+            .select(&[])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn select_nothing_matched() {
+        let list = names(&["github", "bank"]);
+
+        for select in [
+            select(Some("zz"), None, None, None),
+            select(None, None, Some("mail*"), None),
+            select(None, None, None, Some("^mail")),
+        ] {
+            let err = select.select(&list).unwrap_err();
+
+            assert!(matches!(err.kind, error::ErrorKind::NotFound));
+        }
+    }
+}
@@ -0,0 +1,809 @@
+use std::io::Write;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::ValueEnum;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::cli;
+use crate::error;
+use crate::otp;
+use crate::print;
+use crate::types;
+
+/// how often the screen is redrawn when there is no input
+const TICK: Duration = Duration::from_millis(250);
+/// number of characters used by the progress bar of each record
+const BAR_WIDTH: usize = 10;
+
+/// a single labeled input of a form
+///
+/// masked fields are drawn with every character replaced
+struct Field {
+    label: &'static str,
+    value: String,
+    masked: bool,
+}
+
+impl Field {
+    fn new(label: &'static str, value: impl Into<String>) -> Self {
+        Field {
+            label,
+            value: value.into(),
+            masked: false,
+        }
+    }
+
+    fn masked(label: &'static str) -> Self {
+        Field {
+            label,
+            value: String::new(),
+            masked: true,
+        }
+    }
+
+    /// the value as it is drawn in the form
+    fn display(&self) -> String {
+        if self.masked {
+            "*".repeat(self.value.chars().count())
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+/// what a form will do when submitted
+enum FormKind {
+    Add,
+    Edit(String),
+    Rename(String),
+}
+
+/// a popup with a list of fields to fill in
+struct Form {
+    kind: FormKind,
+    fields: Vec<Field>,
+    focus: usize,
+}
+
+impl Form {
+    fn title(&self) -> String {
+        match &self.kind {
+            FormKind::Add => String::from(" add record "),
+            FormKind::Edit(name) => format!(" edit {} ", name),
+            FormKind::Rename(name) => format!(" rename {} ", name),
+        }
+    }
+
+    /// retrieves the value of a field by its label
+    fn value(&self, label: &str) -> &str {
+        self.fields
+            .iter()
+            .find(|f| f.label == label)
+            .map(|f| f.value.trim())
+            .unwrap_or_default()
+    }
+}
+
+/// the current interaction of the app
+enum Mode {
+    Normal,
+    Search,
+    View,
+    Form(Form),
+    ConfirmDrop(String),
+}
+
+/// state of the interactive interface
+struct App {
+    file: types::TotpFile,
+    names: Vec<String>,
+    query: String,
+    mode: Mode,
+    table: TableState,
+    status: Option<String>,
+    quit: bool,
+}
+
+/// parses an optional text field, an empty value is None
+fn optional(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_owned())
+    }
+}
+
+/// parses a comma separated list of tags, skipping empty and repeated tags
+fn tags(value: &str) -> Vec<String> {
+    let mut rtn: Vec<String> = Vec::new();
+
+    for tag in value
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        if !rtn.iter().any(|t| t == tag) {
+            rtn.push(tag.to_owned());
+        }
+    }
+
+    rtn
+}
+
+/// copies text to the clipboard of the terminal
+///
+/// uses the OSC 52 escape sequence which is supported by most terminals
+/// including over ssh
+fn copy_to_clipboard(text: &str) -> error::Result<()> {
+    let mut stdout = std::io::stdout();
+
+    write!(stdout, "\x1b]52;c;{}\x07", BASE64.encode(text))?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// a rect centered in the given area
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    area
+}
+
+impl App {
+    fn new(file: types::TotpFile) -> Self {
+        let mut app = App {
+            file,
+            names: Vec::new(),
+            query: String::new(),
+            mode: Mode::Normal,
+            table: TableState::default(),
+            status: None,
+            quit: false,
+        };
+
+        app.refresh();
+        app
+    }
+
+    /// rebuilds the list of names from the current records and query
+    fn refresh(&mut self) {
+        let query = self.query.to_lowercase();
+        let current = self.selected().cloned();
+
+        self.names = self
+            .file
+            .records
+            .iter()
+            .filter(|(name, record)| {
+                query.is_empty()
+                    || name.to_lowercase().contains(&query)
+                    || record
+                        .issuer
+                        .as_ref()
+                        .is_some_and(|v| v.to_lowercase().contains(&query))
+                    || record
+                        .username
+                        .as_ref()
+                        .is_some_and(|v| v.to_lowercase().contains(&query))
+                    || record
+                        .folder
+                        .as_ref()
+                        .is_some_and(|v| v.to_lowercase().contains(&query))
+                    || record
+                        .tags
+                        .iter()
+                        .any(|v| v.to_lowercase().contains(&query))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let records = &self.file.records;
+
+        self.names
+            .sort_by(|a, b| records[b].pinned.cmp(&records[a].pinned).then(a.cmp(b)));
+
+        if let Some(index) = current.and_then(|name| self.names.iter().position(|n| *n == name)) {
+            self.table.select(Some(index));
+            return;
+        }
+
+        match self.table.selected() {
+            _ if self.names.is_empty() => self.table.select(None),
+            Some(index) if index >= self.names.len() => {
+                self.table.select(Some(self.names.len() - 1))
+            }
+            None => self.table.select(Some(0)),
+            _ => {}
+        }
+    }
+
+    fn selected(&self) -> Option<&String> {
+        self.table
+            .selected()
+            .and_then(|index| self.names.get(index))
+    }
+
+    /// saves the file and reports the result in the status line
+    fn save(&mut self, msg: String) {
+        match self.file.update_file() {
+            Ok(()) => self.status = Some(msg),
+            Err(err) => {
+                self.status = Some(format!(
+                    "failed to save file: {}",
+                    err.message.unwrap_or_else(|| err.kind.to_string())
+                ))
+            }
+        }
+
+        self.refresh();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.names.is_empty() {
+            return;
+        }
+
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.names.len() as isize - 1);
+
+        self.table.select(Some(next as usize));
+    }
+
+    fn copy_selected(&mut self) {
+        let Some(name) = self.selected().cloned() else {
+            return;
+        };
+
+        let code = cli::code_time_now().and_then(|now| self.file.records[&name].code_at(now));
+
+        self.status = Some(match code {
+            Ok(code) => match copy_to_clipboard(&code) {
+                Ok(()) => format!("copied code for {}", name),
+                Err(_) => String::from("failed to copy code"),
+            },
+            Err(err) => err.message.unwrap_or_else(|| err.kind.to_string()),
+        });
+    }
+
+    fn add_form() -> Form {
+        Form {
+            kind: FormKind::Add,
+            fields: vec![
+                Field::new("name", ""),
+                Field::masked("secret"),
+                Field::new("algo", "SHA1"),
+                Field::new("digits", "6"),
+                Field::new("step", "30"),
+                Field::new("issuer", ""),
+                Field::new("username", ""),
+                Field::new("folder", ""),
+                Field::new("tags", ""),
+            ],
+            focus: 0,
+        }
+    }
+
+    fn edit_form(name: &str, record: &types::TotpRecord) -> Form {
+        Form {
+            kind: FormKind::Edit(name.to_owned()),
+            fields: vec![
+                Field::masked("secret"),
+                Field::new("algo", record.algo.as_str()),
+                Field::new("digits", record.digits.to_string()),
+                Field::new("step", record.step.to_string()),
+                Field::new(
+                    "group",
+                    record.group.map(|g| g.to_string()).unwrap_or_default(),
+                ),
+                Field::new("issuer", record.issuer.clone().unwrap_or_default()),
+                Field::new("username", record.username.clone().unwrap_or_default()),
+                Field::new("folder", record.folder.clone().unwrap_or_default()),
+                Field::new("tags", record.tags.join(", ")),
+            ],
+            focus: 0,
+        }
+    }
+
+    /// applies a submitted form, returning an error message if a value is
+    /// invalid
+    fn submit(&mut self, form: &Form) -> Result<String, String> {
+        let algo = |v: &str| otp::Algo::from_str(v, true);
+        let digits = |v: &str| types::Digits::from_str(v);
+        let step = |v: &str| {
+            NonZeroU64::from_str(v).map_err(|_| String::from("step must be greater than zero"))
+        };
+
+        match &form.kind {
+            FormKind::Add => {
+                let name = form.value("name");
+
+                if name.is_empty() {
+                    return Err(String::from("name must not be empty"));
+                }
+
+                if self.file.records.contains_key(name) {
+                    return Err(format!("\"{}\" already exists", name));
+                }
+
+                let secret = cli::Base32::from_str(form.value("secret"))?;
+
+                let mut record = types::TotpRecord::new(
+                    secret.into(),
+                    algo(form.value("algo"))?,
+                    digits(form.value("digits"))?,
+                    step(form.value("step"))?,
+                )
+                .map_err(|err| err.message.unwrap_or_default())?;
+                record.issuer = optional(form.value("issuer"));
+                record.username = optional(form.value("username"));
+                record.folder = types::normalize_folder(form.value("folder"));
+                record.tags = tags(form.value("tags"));
+
+                self.file.records.insert(name.to_owned(), record);
+
+                Ok(format!("added {}", name))
+            }
+            FormKind::Edit(name) => {
+                let secret = match form.value("secret") {
+                    "" => None,
+                    v => Some(cli::Base32::from_str(v)?),
+                };
+                let algo = algo(form.value("algo"))?;
+                let digits = digits(form.value("digits"))?;
+                let step = step(form.value("step"))?;
+                let group = match form.value("group") {
+                    "" => None,
+                    v => NonZeroUsize::new(
+                        v.parse()
+                            .map_err(|_| String::from("group must be a number"))?,
+                    ),
+                };
+
+                let Some(record) = self.file.records.get_mut(name) else {
+                    return Err(format!("\"{}\" no longer exists", name));
+                };
+
+                if let Some(secret) = secret {
+                    record.secret = secret.into();
+                }
+
+                record.algo = algo;
+                record.digits = digits;
+                record.step = step;
+                record.group = group;
+                record.issuer = optional(form.value("issuer"));
+                record.username = optional(form.value("username"));
+                record.folder = types::normalize_folder(form.value("folder"));
+                record.tags = tags(form.value("tags"));
+                record.touch();
+
+                Ok(format!("updated {}", name))
+            }
+            FormKind::Rename(name) => {
+                let renamed = form.value("new name");
+
+                if renamed.is_empty() {
+                    return Err(String::from("name must not be empty"));
+                }
+
+                if self.file.records.contains_key(renamed) {
+                    return Err(format!("\"{}\" already exists", renamed));
+                }
+
+                let Some((index, _, mut record)) = self.file.records.shift_remove_full(name) else {
+                    return Err(format!("\"{}\" no longer exists", name));
+                };
+
+                record.touch();
+
+                self.file
+                    .records
+                    .shift_insert(index, renamed.to_owned(), record);
+
+                Ok(format!("renamed {} to {}", name, renamed))
+            }
+        }
+    }
+
+    fn handle_normal(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::Char('/') => self.mode = Mode::Search,
+            KeyCode::Enter | KeyCode::Char('c') => self.copy_selected(),
+            KeyCode::Char('v') if self.selected().is_some() => self.mode = Mode::View,
+            KeyCode::Char('a') => self.mode = Mode::Form(Self::add_form()),
+            KeyCode::Char('e') => {
+                if let Some(name) = self.selected() {
+                    let form = Self::edit_form(name, &self.file.records[name]);
+
+                    self.mode = Mode::Form(form);
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(name) = self.selected() {
+                    self.mode = Mode::Form(Form {
+                        kind: FormKind::Rename(name.clone()),
+                        fields: vec![Field::new("new name", name.as_str())],
+                        focus: 0,
+                    });
+                }
+            }
+            KeyCode::Char('p') => {
+                if let Some(name) = self.selected().cloned() {
+                    let record = self.file.records.get_mut(&name).unwrap();
+                    record.pinned = !record.pinned;
+                    record.touch();
+
+                    let msg = if record.pinned { "pinned" } else { "unpinned" };
+
+                    self.save(format!("{} {}", msg, name));
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some(name) = self.selected() {
+                    self.mode = Mode::ConfirmDrop(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_search(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.query.clear();
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Enter => self.mode = Mode::Normal,
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Char(c) => self.query.push(c),
+            _ => {}
+        }
+
+        self.refresh();
+    }
+
+    fn handle_form(&mut self, mut form: Form, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                match self.submit(&form) {
+                    Ok(msg) => self.save(msg),
+                    Err(msg) => {
+                        self.status = Some(msg);
+                        self.mode = Mode::Form(form);
+                    }
+                }
+
+                return;
+            }
+            KeyCode::Tab | KeyCode::Down => form.focus = (form.focus + 1) % form.fields.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                form.focus = (form.focus + form.fields.len() - 1) % form.fields.len()
+            }
+            KeyCode::Backspace => {
+                form.fields[form.focus].value.pop();
+            }
+            KeyCode::Char(c) => form.fields[form.focus].value.push(c),
+            _ => {}
+        }
+
+        self.mode = Mode::Form(form);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal(key),
+            Mode::Search => {
+                self.mode = Mode::Search;
+                self.handle_search(key);
+            }
+            Mode::View => {}
+            Mode::Form(form) => self.handle_form(form, key),
+            Mode::ConfirmDrop(name) => {
+                if let KeyCode::Char('y') = key.code {
+                    self.file.records.shift_remove(&name);
+                    self.save(format!("dropped {}", name));
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, now: u64) {
+        let [search_area, table_area, gauge_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let search_style = if let Mode::Search = self.mode {
+            Style::new().add_modifier(Modifier::BOLD)
+        } else {
+            Style::new()
+        };
+
+        frame.render_widget(
+            Paragraph::new(self.query.as_str())
+                .style(search_style)
+                .block(Block::default().borders(Borders::ALL).title(" search ")),
+            search_area,
+        );
+
+        let rows = self.names.iter().map(|name| {
+            let record = &self.file.records[name];
+            let (_, left) = record.counter_at(now);
+
+            let (code, bar) = match record.code_at(now) {
+                Ok(code) => {
+                    let code = match record.group {
+                        Some(size) => otp::group_code(&code, size.get()),
+                        None => code,
+                    };
+
+                    (
+                        code,
+                        format!(
+                            "{} {:>3}s",
+                            print::progress_bar(left, record.step.get(), BAR_WIDTH),
+                            left
+                        ),
+                    )
+                }
+                Err(_) if record.family.requires_pin() => {
+                    (String::from("pin required"), String::new())
+                }
+                Err(_) => (String::from("-"), String::new()),
+            };
+
+            Row::new(vec![
+                Cell::from(if record.pinned {
+                    format!("* {}", name)
+                } else {
+                    name.clone()
+                }),
+                Cell::from(record.issuer.as_deref().unwrap_or_default()),
+                Cell::from(record.username.as_deref().unwrap_or_default()),
+                Cell::from(code),
+                Cell::from(bar),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(14),
+                Constraint::Length(BAR_WIDTH as u16 + 5),
+            ],
+        )
+        .header(
+            Row::new(vec!["name", "issuer", "username", "code", "remaining"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " {} ({}/{}) ",
+            self.file.path.display(),
+            self.names.len(),
+            self.file.records.len()
+        )));
+
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        if let Some(record) = self.selected().map(|name| &self.file.records[name]) {
+            let (_, left) = record.counter_at(now);
+            let step = record.step.get();
+
+            frame.render_widget(
+                Gauge::default()
+                    .ratio(left as f64 / step as f64)
+                    .label(format!("{}s left", left)),
+                gauge_area,
+            );
+        }
+
+        let help = match &self.status {
+            Some(status) => status.clone(),
+            None => String::from(
+                "q quit  / search  enter copy  v view  a add  e edit  r rename  p pin  d drop",
+            ),
+        };
+
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        match &self.mode {
+            Mode::View => self.draw_view(frame),
+            Mode::Form(form) => Self::draw_form(frame, form),
+            Mode::ConfirmDrop(name) => {
+                let area = centered(frame.area(), 50, 3);
+
+                frame.render_widget(Clear, area);
+                frame.render_widget(
+                    Paragraph::new(format!("drop {}? y/n", name))
+                        .block(Block::default().borders(Borders::ALL).title(" drop ")),
+                    area,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn draw_view(&self, frame: &mut Frame) {
+        let Some(name) = self.selected() else {
+            return;
+        };
+
+        let record = &self.file.records[name];
+        let mut lines = vec![
+            Line::from(format!(
+                "  base32: {}",
+                data_encoding::BASE32.encode(&record.secret)
+            )),
+            Line::from(format!("  family: {}", record.family)),
+            Line::from(format!("    algo: {}", record.algo.as_str())),
+            Line::from(format!("  digits: {}", record.digits)),
+            Line::from(format!("    step: {}s", record.step)),
+            Line::from(format!("  encode: {}", record.encoder)),
+        ];
+
+        if let Some(group) = record.group {
+            lines.push(Line::from(format!("   group: {}", group)));
+        }
+
+        if let Some(issuer) = &record.issuer {
+            lines.push(Line::from(format!("  issuer: {}", issuer)));
+        }
+
+        if let Some(username) = &record.username {
+            lines.push(Line::from(format!("username: {}", username)));
+        }
+
+        if let Some(folder) = &record.folder {
+            lines.push(Line::from(format!("  folder: {}", folder)));
+        }
+
+        if !record.tags.is_empty() {
+            lines.push(Line::from(format!("    tags: {}", record.tags.join(", "))));
+        }
+
+        if let Some(url) = &record.url {
+            lines.push(Line::from(format!("     url: {}", url)));
+        }
+
+        for (key, value) in &record.fields {
+            lines.push(Line::from(format!("{:>8}: {}", key, value)));
+        }
+
+        for (label, time) in [
+            (" created", record.created_at),
+            (" updated", record.updated_at),
+            ("    used", record.last_used_at),
+        ] {
+            if let Some(time) = time {
+                lines.push(Line::from(format!(
+                    "{}: {}",
+                    label,
+                    print::format_timestamp(time)
+                )));
+            }
+        }
+
+        if let Some(notes) = &record.notes {
+            lines.push(Line::from("   notes:"));
+
+            for line in notes.lines() {
+                lines.push(Line::from(format!("    {}", line)));
+            }
+        }
+
+        let width = frame.area().width.saturating_sub(4).min(80);
+        let area = centered(frame.area(), width, lines.len() as u16 + 2);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ", name)),
+            ),
+            area,
+        );
+    }
+
+    fn draw_form(frame: &mut Frame, form: &Form) {
+        let lines: Vec<Line> = form
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let style = if index == form.focus {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new()
+                };
+
+                Line::from(vec![
+                    Span::raw(format!("{:>9}: ", field.label)),
+                    Span::styled(format!("{}_", field.display()), style),
+                ])
+            })
+            .collect();
+
+        let width = frame.area().width.saturating_sub(4).min(70);
+        let area = centered(frame.area(), width, lines.len() as u16 + 2);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(form.title())
+                    .title_bottom(" tab next  enter save  esc cancel "),
+            ),
+            area,
+        );
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> error::Result<()> {
+        while !self.quit {
+            let now = cli::code_time_now()?;
+
+            terminal.draw(|frame| self.draw(frame, now))?;
+
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if !matches!(self.mode, Mode::Form(_)) {
+                        self.status = None;
+                    }
+
+                    self.handle_key(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// runs the interactive interface for the given file until the user quits
+pub fn run(file: types::TotpFile) -> error::Result<()> {
+    let mut app = App::new(file);
+    let mut terminal = ratatui::init();
+
+    let result = app.run(&mut terminal);
+
+    ratatui::restore();
+
+    result
+}
@@ -1,11 +1,17 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::num::{NonZeroU64, NonZeroUsize};
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
 use crate::chacha;
-use crate::cli;
 use crate::error::{Error, ErrorKind, Result};
 use crate::otp;
+use crate::platform::{Storage, System};
+use crate::secret::{SecretBytes, SecretString};
+use crate::util;
 
 ///default algo value for de/serialization
 fn default_algo() -> otp::Algo {
@@ -13,13 +19,87 @@ fn default_algo() -> otp::Algo {
 }
 
 /// default digits value for de/serialization
-fn default_digits() -> u32 {
-    6
+fn default_digits() -> Digits {
+    Digits(6)
 }
 
 /// default step value for de/serialization
-fn default_step() -> u64 {
-    30
+fn default_step() -> NonZeroU64 {
+    NonZeroU64::new(30).unwrap()
+}
+
+/// deserializes a secret that must not be empty
+fn deserialize_secret<'de, D>(deserializer: D) -> std::result::Result<SecretBytes, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let secret = SecretBytes::deserialize(deserializer)?;
+
+    if secret.is_empty() {
+        Err(serde::de::Error::invalid_length(0, &"a non-empty secret"))
+    } else {
+        Ok(secret)
+    }
+}
+
+/// the number of digits in a generated code
+///
+/// guaranteed to be between [otp::MIN_DIGITS] and [otp::MAX_DIGITS]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Digits(u32);
+
+impl Digits {
+    pub fn new(digits: u32) -> Result<Digits> {
+        if (otp::MIN_DIGITS..=otp::MAX_DIGITS).contains(&digits) {
+            Ok(Digits(digits))
+        } else {
+            Err(Error::new(ErrorKind::InvalidArgument).with_message(format!(
+                "digits must be between {} and {}",
+                otp::MIN_DIGITS,
+                otp::MAX_DIGITS
+            )))
+        }
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Digits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::str::FromStr for Digits {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let Ok(parsed) = s.parse() else {
+            return Err(String::from("digits is not a valid unsigned integer"));
+        };
+
+        Digits::new(parsed).map_err(|err| err.message.unwrap_or_default())
+    }
+}
+
+impl<'de> Deserialize<'de> for Digits {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let digits = u32::deserialize(deserializer)?;
+
+        Digits::new(digits).map_err(|_| {
+            let expected = format!("digits between {} and {}", otp::MIN_DIGITS, otp::MAX_DIGITS);
+
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Unsigned(digits as u64),
+                &expected.as_str(),
+            )
+        })
+    }
 }
 
 /// represents a totp credential
@@ -27,21 +107,201 @@ fn default_step() -> u64 {
 /// secret, algo, digits, and step are all required in order to properly
 /// generate totp codes. the issuer and username are also provided to help
 /// with identifying each record.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// the secret must not be empty, digits must be within a valid range, and
+/// step must not be zero. this is enforced when creating a record with
+/// [TotpRecord::new] and when deserializing.
+///
+/// the secret is zeroed when the record is dropped and will not be shown
+/// when the record is Debug printed
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TotpRecord {
-    pub secret: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_secret")]
+    pub secret: SecretBytes,
+    /// totp, ocra, or one of the pin based families
+    #[serde(default)]
+    pub family: otp::Family,
     #[serde(default = "default_algo")]
     pub algo: otp::Algo,
     #[serde(default = "default_digits")]
-    pub digits: u32,
+    pub digits: Digits,
     #[serde(default = "default_step")]
-    pub step: u64,
+    pub step: NonZeroU64,
+    /// how the code is rendered
+    #[serde(default)]
+    pub encoder: otp::Encoder,
+    /// splits the displayed code into groups of the given size
+    #[serde(default)]
+    pub group: Option<NonZeroUsize>,
+    /// the unix epoch that counting starts from
+    #[serde(default)]
+    pub t0: u64,
+    /// seconds added to the clock to correct for known skew
+    #[serde(default)]
+    pub time_offset: i64,
+    /// used by the motp and yandex families, prompted for if not stored
+    #[serde(default)]
+    pub pin: Option<SecretString>,
     pub issuer: Option<String>,
     pub username: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default)]
+    pub recovery: Vec<RecoveryCode>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// a single use code provided when enrolling an account
+///
+/// the code is zeroed when dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCode {
+    pub code: SecretString,
+    #[serde(default)]
+    pub used: bool,
+}
+
+impl TotpRecord {
+    /// creates a new record without an issuer or username
+    ///
+    /// the created and updated times are set to the current time. will
+    /// return an error if the secret is empty
+    pub fn new(
+        secret: SecretBytes,
+        algo: otp::Algo,
+        digits: Digits,
+        step: NonZeroU64,
+    ) -> Result<TotpRecord> {
+        if secret.is_empty() {
+            return Err(
+                Error::new(ErrorKind::InvalidArgument).with_message("secret must not be empty")
+            );
+        }
+
+        let now = util::unix_epoch_sec_now();
+
+        Ok(TotpRecord {
+            secret,
+            family: otp::Family::Totp,
+            algo,
+            digits,
+            step,
+            encoder: otp::Encoder::Decimal,
+            group: None,
+            t0: 0,
+            time_offset: 0,
+            pin: None,
+            issuer: None,
+            username: None,
+            tags: Vec::new(),
+            folder: None,
+            notes: None,
+            url: None,
+            fields: BTreeMap::new(),
+            recovery: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            last_used_at: None,
+            pinned: false,
+        })
+    }
+
+    /// sets when the record was last changed to the current time
+    pub fn touch(&mut self) {
+        self.updated_at = util::unix_epoch_sec_now();
+    }
+
+    /// adjusts the given unix epoch by the clock offset of the record
+    pub fn adjusted_time(&self, time: u64) -> u64 {
+        time.saturating_add_signed(self.time_offset)
+    }
+
+    /// generates the code of the record for the given unix epoch
+    ///
+    /// the time is adjusted by the clock offset and t0 of the record. will
+    /// return an error for ocra records or if a required pin is missing
+    pub fn code_at(&self, time: u64) -> Result<String> {
+        let (counter, _) = self.counter_at(time);
+
+        match &self.family {
+            otp::Family::Totp => otp::generate_code(
+                &self.algo,
+                &self.secret,
+                self.digits,
+                &self.encoder,
+                &counter.to_be_bytes(),
+            ),
+            otp::Family::Ocra(_) => Err(Error::new(ErrorKind::InvalidArgument)
+                .with_message("challenge-response records do not generate codes")),
+            otp::Family::Motp | otp::Family::Yandex => {
+                let Some(pin) = self.pin.as_ref() else {
+                    return Err(Error::new(ErrorKind::InvalidArgument)
+                        .with_message("pin required to generate codes"));
+                };
+
+                if let otp::Family::Motp = self.family {
+                    Ok(otp::motp_code(&self.secret, pin, self.digits, counter))
+                } else {
+                    otp::yandex_code(&self.secret, pin, self.digits, counter)
+                }
+            }
+        }
+    }
+
+    /// verifies a code of the record for the given unix epoch
+    ///
+    /// checks up to window steps before and after the given time to allow
+    /// for clock drift
+    pub fn verify_at(&self, code: &str, time: u64, window: u64) -> Result<bool> {
+        let step = self.step.get();
+
+        for offset in 0..=window {
+            let delta = offset.saturating_mul(step);
+
+            if self.code_at(time.saturating_add(delta))? == code {
+                return Ok(true);
+            }
+
+            if offset != 0 && delta <= time && self.code_at(time - delta)? == code {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// calculates the counter for the given unix epoch
+    ///
+    /// returns the counter and the number of seconds until the next step.
+    /// times before t0 are treated as t0
+    pub fn counter_at(&self, time: u64) -> (u64, u64) {
+        let step = self.step.get();
+        let elapsed = self.adjusted_time(time).saturating_sub(self.t0);
+
+        (elapsed / step, step - (elapsed % step))
+    }
 }
 
-/// type alias for hashmap records with a string name
-pub type TotpRecordDict = HashMap<String, TotpRecord>;
+/// type alias for records with a string name
+///
+/// records keep the order they were inserted in which is also the order
+/// they are written to a file
+pub type TotpRecordDict = IndexMap<String, TotpRecord>;
 
 /// accepted file types for a totp file
 pub enum TotpFileType {
@@ -50,6 +310,266 @@ pub enum TotpFileType {
     TOTP,
 }
 
+impl TotpFileType {
+    /// determines the file type from the extension of the given path
+    pub fn from_path<P>(path: P) -> Result<TotpFileType>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let Some(ext) = path.as_ref().extension() else {
+            return Err(Error::new(ErrorKind::InvalidExtension)
+                .with_message("no file extension found for given path"));
+        };
+
+        let ext = ext.to_ascii_lowercase();
+
+        if ext.eq("yaml") || ext.eq("yml") {
+            Ok(TotpFileType::YAML)
+        } else if ext.eq("json") {
+            Ok(TotpFileType::JSON)
+        } else if ext.eq("totp") {
+            Ok(TotpFileType::TOTP)
+        } else {
+            Err(Error::new(ErrorKind::InvalidExtension)
+                .with_message("unknown file extension given from path"))
+        }
+    }
+}
+
+/// the ways an encrypted totp file can be stored
+///
+/// blob will encrypt all records together as a single value. per record
+/// will leave the names, issuers, and usernames readable and encrypt each
+/// record separately so that the file can be listed without the secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "cli",
+    derive(clap::ValueEnum),
+    value(rename_all = "kebab-case")
+)]
+pub enum TotpLayout {
+    Blob,
+    PerRecord,
+}
+
+/// header used to identify a file using the per record layout
+///
+/// blob files start with a random nonce so there is no header to check for
+const PER_RECORD_HEADER: &[u8] = b"TOTP-PER-RECORD\n";
+
+/// the readable information of a record
+///
+/// available without decrypting a per record file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordMeta {
+    pub issuer: Option<String>,
+    pub username: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl From<&TotpRecord> for RecordMeta {
+    fn from(record: &TotpRecord) -> Self {
+        RecordMeta {
+            issuer: record.issuer.clone(),
+            username: record.username.clone(),
+            tags: record.tags.clone(),
+            folder: record.folder.clone(),
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            last_used_at: record.last_used_at,
+            pinned: record.pinned,
+        }
+    }
+}
+
+/// trims surrounding whitespace and slashes from a folder path
+///
+/// returns None if nothing is left
+pub fn normalize_folder(folder: &str) -> Option<String> {
+    let trimmed = folder.trim().trim_matches('/');
+
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_owned())
+    }
+}
+
+/// checks if a folder is the same as or inside of a parent folder
+pub fn in_folder(folder: &str, parent: &str) -> bool {
+    match folder.strip_prefix(parent) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// a record that has been read from a file but not parsed
+///
+/// the value will be an error if the record could not be read, such as an
+/// individually encrypted record failing to decrypt
+pub struct RawRecord {
+    pub name: String,
+    pub value: std::result::Result<serde_json::Value, String>,
+}
+
+/// type alias for record metadata with a string name
+///
+/// in the same order as the records of the file
+pub type RecordMetaDict = IndexMap<String, RecordMeta>;
+
+/// data encrypted with its own nonce
+///
+/// both values are base64 encoded. the aad given when sealing is not stored
+/// and must be given again to open the data
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    data: String,
+}
+
+impl Sealed {
+    /// encrypts the given data with a new nonce
+    fn seal(key: &chacha::Key, data: &[u8], aad: &[u8]) -> Result<Sealed> {
+        let nonce = chacha::make_nonce()?;
+        let encrypted = chacha::encrypt_data_aad(key, &nonce, data, aad)?;
+
+        Ok(Sealed {
+            nonce: BASE64.encode(nonce),
+            data: BASE64.encode(encrypted),
+        })
+    }
+
+    /// decrypts the stored data
+    fn open(&self, key: &chacha::Key, aad: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let invalid = || {
+            Error::new(ErrorKind::ChaChaError)
+                .with_message("invalid file format for encrypted record")
+        };
+
+        let nonce_bytes = BASE64.decode(&self.nonce).map_err(|_| invalid())?;
+        let encrypted = BASE64.decode(&self.data).map_err(|_| invalid())?;
+        let nonce: chacha::Nonce = nonce_bytes.try_into().map_err(|_| invalid())?;
+
+        Ok(Zeroizing::new(chacha::decrypt_data_aad(
+            key, &nonce, encrypted, aad,
+        )?))
+    }
+}
+
+/// a record stored in a per record file
+///
+/// the meta is stored as a json string and the name and the stored string
+/// are used as the aad of the record so that neither can be changed or
+/// swapped with another record without failing to decrypt. authenticating
+/// the stored string instead of [RecordMeta] allows fields to be added to
+/// the meta without breaking existing files
+#[derive(Serialize, Deserialize)]
+struct SealedRecord {
+    meta: String,
+    record: Sealed,
+}
+
+impl SealedRecord {
+    /// the aad used when encrypting a record with the given name and meta
+    fn aad(name: &str, meta: &str) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&(name, meta))?)
+    }
+
+    /// encrypts a record with its name and meta
+    fn seal(key: &chacha::Key, name: &str, record: &TotpRecord) -> Result<SealedRecord> {
+        let meta = serde_json::to_string(&RecordMeta::from(record))?;
+        let data = Zeroizing::new(serde_json::to_vec(record)?);
+        let sealed = Sealed::seal(key, &data, &Self::aad(name, &meta)?)?;
+
+        Ok(SealedRecord {
+            meta,
+            record: sealed,
+        })
+    }
+
+    /// parses the readable information of the record
+    ///
+    /// the meta is not authenticated until the record is opened
+    fn meta(&self) -> Result<RecordMeta> {
+        Ok(serde_json::from_str(&self.meta)?)
+    }
+
+    /// decrypts the record with the given name
+    ///
+    /// fails if the name or meta are not the same as when it was sealed
+    fn open(&self, key: &chacha::Key, name: &str) -> Result<Zeroizing<Vec<u8>>> {
+        self.record
+            .open(key, &Self::aad(name, &self.meta)?)
+            .map_err(|_| {
+                Error::new(ErrorKind::ChaChaError).with_message(format!(
+                    "failed to decrypt record \"{}\", it may have been modified",
+                    name
+                ))
+            })
+    }
+}
+
+/// the contents of a per record file after the header
+///
+/// check is an encrypted empty value used to verify the key when the file
+/// does not contain any records. the names and nonces of the records are
+/// used as its aad so that records cannot be removed, reordered, or replaced
+/// with a copy from an older file without failing to decrypt
+#[derive(Serialize, Deserialize)]
+struct PerRecordContents {
+    check: Sealed,
+    records: IndexMap<String, SealedRecord>,
+}
+
+impl PerRecordContents {
+    /// the aad of the check value for the given records
+    fn check_aad(records: &IndexMap<String, SealedRecord>) -> Result<Vec<u8>> {
+        let list: Vec<(&str, &str)> = records
+            .iter()
+            .map(|(name, sealed)| (name.as_str(), sealed.record.nonce.as_str()))
+            .collect();
+
+        Ok(serde_json::to_vec(&list)?)
+    }
+
+    /// seals each of the given records and then the check value
+    fn seal(key: &chacha::Key, records: &TotpRecordDict) -> Result<PerRecordContents> {
+        let mut sealed = IndexMap::with_capacity(records.len());
+
+        for (name, record) in records {
+            sealed.insert(name.clone(), SealedRecord::seal(key, name, record)?);
+        }
+
+        Ok(PerRecordContents {
+            check: Sealed::seal(key, &[], &Self::check_aad(&sealed)?)?,
+            records: sealed,
+        })
+    }
+
+    /// verifies the key and the list of records with the check value
+    fn open_check(&self, key: &chacha::Key) -> Result<()> {
+        self.check
+            .open(key, &Self::check_aad(&self.records)?)
+            .map_err(|_| {
+                Error::new(ErrorKind::ChaChaError)
+                    .with_message("invalid secret or the records of the file were modified")
+            })?;
+
+        Ok(())
+    }
+}
+
 /// a file that stores totp credentials
 ///
 /// stores the path, file type, records, and potential cryptography key for a
@@ -62,51 +582,85 @@ pub enum TotpFileType {
 /// stored so the user does not have to provide the password twice. it is not
 /// the actual secret provided but what is generated from [chacha::make_key]
 /// function
+///
+/// the layout is only used for totp files and is ignored otherwise
 pub struct TotpFile {
     pub path: std::path::PathBuf,
     pub file_type: TotpFileType,
+    pub layout: TotpLayout,
     pub records: TotpRecordDict,
     pub key: Option<chacha::Key>,
 }
 
 impl TotpFile {
-    /// attempts to parse and decrypt the data stored in the file
+    /// splits the data stored in a blob file into the nonce and encrypted data
     ///
     /// the nonce is stored in the first 24 bytes of the file. the rest is the
-    /// encrypted data
-    fn decrypt(key: &chacha::Key, data: Vec<u8>) -> Result<TotpRecordDict> {
-        let mut encrypted: Vec<u8> = Vec::with_capacity(data.len() - chacha::NONCE_LEN);
-        let mut nonce = [0u8; chacha::NONCE_LEN];
-        let mut iter = data.into_iter();
-
-        for i in 0..nonce.len() {
-            if let Some(byte) = iter.next() {
-                nonce[i] = byte;
-            } else {
-                return Err(Error::new(ErrorKind::ChaChaError)
-                    .with_message("invalid file format for encrypted file"));
-            }
-        }
+    /// encrypted data which must be long enough to hold the tag
+    fn split_blob(data: &[u8]) -> Result<(chacha::Nonce, &[u8])> {
+        let invalid = || {
+            Error::new(ErrorKind::ChaChaError)
+                .with_message("file is too short to be a valid encrypted file")
+        };
 
-        while let Some(byte) = iter.next() {
-            encrypted.push(byte);
+        if data.len() < chacha::NONCE_LEN + chacha::TAG_LEN {
+            return Err(invalid());
         }
 
-        let decrypted = chacha::decrypt_data(&key, &nonce, &encrypted)?;
+        let (nonce, encrypted) = data.split_at(chacha::NONCE_LEN);
+
+        Ok((nonce.try_into().map_err(|_| invalid())?, encrypted))
+    }
+
+    /// attempts to parse and decrypt the data stored in the file
+    fn decrypt(key: &chacha::Key, data: &[u8]) -> Result<TotpRecordDict> {
+        let (nonce, encrypted) = Self::split_blob(data)?;
+        let decrypted = Zeroizing::new(chacha::decrypt_data(key, &nonce, encrypted)?);
         let records = serde_json::from_slice(&decrypted)?;
 
         Ok(records)
     }
 
+    /// attempts to parse and decrypt the records of a per record file
+    ///
+    /// the check value is decrypted first so that an invalid key is caught
+    /// even if there are no records
+    fn decrypt_per_record(key: &chacha::Key, data: &[u8]) -> Result<TotpRecordDict> {
+        let contents: PerRecordContents = serde_json::from_slice(data)?;
+        let mut records = IndexMap::with_capacity(contents.records.len());
+
+        contents.open_check(key)?;
+
+        for (name, sealed) in contents.records {
+            let decrypted = sealed.open(key, &name)?;
+
+            records.insert(name, serde_json::from_slice(&decrypted)?);
+        }
+
+        Ok(records)
+    }
+
+    /// encrypts each of the given records separately
+    ///
+    /// the header is stored first followed by the json contents
+    fn encrypt_per_record(key: &chacha::Key, records: &TotpRecordDict) -> Result<Vec<u8>> {
+        let contents = PerRecordContents::seal(key, records)?;
+
+        let mut rtn = PER_RECORD_HEADER.to_vec();
+        serde_json::to_writer(&mut rtn, &contents)?;
+
+        Ok(rtn)
+    }
+
     /// encrypts the given records
     ///
     /// it will create a byte vector with the nonce stored in the first 24
     /// bytes and then store the encrypted data in the rest.
     fn encrypt(key: &chacha::Key, records: &TotpRecordDict) -> Result<Vec<u8>> {
         let nonce = chacha::make_nonce()?;
-        let data = serde_json::to_vec(records)?;
+        let data = Zeroizing::new(serde_json::to_vec(records)?);
 
-        let encrypted = chacha::encrypt_data(&key, &nonce, &data)?;
+        let encrypted = chacha::encrypt_data(key, &nonce, &*data)?;
         let mut contents = Vec::with_capacity(nonce.len() + encrypted.len());
 
         for byte in nonce {
@@ -130,68 +684,192 @@ impl TotpFile {
         Ok(std::io::BufReader::new(file))
     }
 
-    /// helper to create an io writer for a given file
-    #[inline]
-    fn get_writer<P>(path: P) -> Result<impl std::io::Write>
+    /// creates a TotpFile struct from the contents of a file
+    ///
+    /// the type of file is determined from the extension of the path. if
+    /// the file is encrypted then get_key is called with the path to
+    /// retrieve the key used to encrypt the data. get_key is not called for
+    /// json or yaml files
+    pub fn from_bytes_with<P, F>(path: P, data: &[u8], get_key: F) -> Result<TotpFile>
     where
         P: AsRef<std::path::Path>,
+        F: FnOnce(&std::path::Path) -> Result<chacha::Key>,
     {
-        let file = std::fs::OpenOptions::new().write(true).open(path)?;
-        Ok(std::io::BufWriter::new(file))
+        let file_type = TotpFileType::from_path(&path)?;
+
+        let (layout, records, key) = match file_type {
+            TotpFileType::YAML => (TotpLayout::Blob, serde_yml::from_slice(data)?, None),
+            TotpFileType::JSON => (TotpLayout::Blob, serde_json::from_slice(data)?, None),
+            TotpFileType::TOTP => {
+                let key = get_key(path.as_ref())?;
+
+                if let Some(body) = data.strip_prefix(PER_RECORD_HEADER) {
+                    let records = Self::decrypt_per_record(&key, body)?;
+
+                    (TotpLayout::PerRecord, records, Some(key))
+                } else {
+                    let records = Self::decrypt(&key, data)?;
+
+                    (TotpLayout::Blob, records, Some(key))
+                }
+            }
+        };
+
+        Ok(TotpFile {
+            path: path.as_ref().to_owned(),
+            file_type,
+            layout,
+            records,
+            key,
+        })
+    }
+
+    /// creates a TotpFile struct from a path in the given storage
+    ///
+    /// see [TotpFile::from_bytes_with] for how the contents are handled
+    pub fn from_storage_with<S, P, F>(storage: &S, path: P, get_key: F) -> Result<TotpFile>
+    where
+        S: Storage + ?Sized,
+        P: AsRef<std::path::Path>,
+        F: FnOnce(&std::path::Path) -> Result<chacha::Key>,
+    {
+        TotpFileType::from_path(&path)?;
+
+        let data = Zeroizing::new(storage.read(path.as_ref())?);
+
+        Self::from_bytes_with(path, &data, get_key)
     }
 
     /// creates a TotpFile struct from a given path
     ///
     /// if the file provided as a totp extension then it will treat it as an
-    /// encrpyted file and will prompt the user for the secret used to
-    /// encrypt the data on the file
-    pub fn from_path<P>(path: P) -> Result<TotpFile>
+    /// encrpyted file and will call get_key with the path to retrieve the
+    /// key used to encrypt the data on the file. get_key is not called for
+    /// json or yaml files
+    pub fn from_path_with<P, F>(path: P, get_key: F) -> Result<TotpFile>
     where
         P: AsRef<std::path::Path>,
+        F: FnOnce(&std::path::Path) -> Result<chacha::Key>,
     {
-        if let Some(ext) = path.as_ref().extension() {
-            let ext = ext.to_ascii_lowercase();
+        Self::from_storage_with(&System, path, get_key)
+    }
 
-            if ext.eq("yaml") || ext.eq("yml") {
-                let records = serde_yml::from_reader(Self::get_reader(&path)?)?;
+    /// creates a TotpFile struct from a given path and secret
+    ///
+    /// the secret is only used if the file is encrypted
+    pub fn from_path_secret<P, S>(path: P, secret: S) -> Result<TotpFile>
+    where
+        P: AsRef<std::path::Path>,
+        S: AsRef<[u8]>,
+    {
+        Self::from_path_with(path, |_| chacha::make_key(secret))
+    }
 
-                Ok(TotpFile {
-                    path: path.as_ref().to_owned(),
-                    file_type: TotpFileType::YAML,
-                    records,
-                    key: None,
-                })
-            } else if ext.eq("json") {
-                let records = serde_json::from_reader(Self::get_reader(&path)?)?;
-
-                Ok(TotpFile {
-                    path: path.as_ref().to_owned(),
-                    file_type: TotpFileType::JSON,
-                    records,
-                    key: None,
-                })
-            } else if ext.eq("totp") {
-                let key = {
-                    let secret = cli::get_input("secret")?;
-                    chacha::make_key(&secret)?
-                };
+    /// reads the records of a given path without parsing them
+    ///
+    /// used to inspect files that may contain invalid records. the header and
+    /// authentication tag of encrypted files are still verified and will
+    /// return an error if invalid. get_key is called the same as
+    /// [TotpFile::from_path_with]. the layout of the file is returned along
+    /// with the records
+    pub fn read_raw_with<P, F>(path: P, get_key: F) -> Result<(TotpLayout, Vec<RawRecord>)>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnOnce(&std::path::Path) -> Result<chacha::Key>,
+    {
+        type RawDict = serde_json::Map<String, serde_json::Value>;
+
+        let dict: RawDict = match TotpFileType::from_path(&path)? {
+            TotpFileType::YAML => serde_yml::from_reader(Self::get_reader(&path)?)?,
+            TotpFileType::JSON => serde_json::from_reader(Self::get_reader(&path)?)?,
+            TotpFileType::TOTP => {
                 let data = std::fs::read(&path)?;
-                let records = Self::decrypt(&key, data)?;
 
-                Ok(TotpFile {
-                    path: path.as_ref().to_owned(),
-                    file_type: TotpFileType::TOTP,
-                    records,
-                    key: Some(key),
-                })
-            } else {
-                Err(Error::new(ErrorKind::InvalidExtension)
-                    .with_message("unknown file extension given from path"))
+                if let Some(body) = data.strip_prefix(PER_RECORD_HEADER) {
+                    let contents: PerRecordContents = serde_json::from_slice(body)?;
+                    let key = get_key(path.as_ref())?;
+                    let mut rtn = Vec::with_capacity(contents.records.len());
+
+                    contents.open_check(&key)?;
+
+                    for (name, sealed) in contents.records {
+                        let value = match sealed.open(&key, &name) {
+                            Ok(decrypted) => {
+                                serde_json::from_slice(&decrypted).map_err(|err| err.to_string())
+                            }
+                            Err(_err) => Err(String::from("failed to decrypt record")),
+                        };
+
+                        rtn.push(RawRecord { name, value });
+                    }
+
+                    return Ok((TotpLayout::PerRecord, rtn));
+                }
+
+                let (nonce, encrypted) = Self::split_blob(&data)?;
+                let key = get_key(path.as_ref())?;
+                let decrypted = Zeroizing::new(chacha::decrypt_data(&key, &nonce, encrypted)?);
+
+                serde_json::from_slice(&decrypted)?
             }
-        } else {
-            Err(Error::new(ErrorKind::InvalidExtension)
-                .with_message("no file extension found for given path"))
+        };
+
+        Ok((
+            TotpLayout::Blob,
+            dict.into_iter()
+                .map(|(name, value)| RawRecord {
+                    name,
+                    value: Ok(value),
+                })
+                .collect(),
+        ))
+    }
+
+    /// retrieves the readable information of records from a given path
+    ///
+    /// json and yaml files are read as normal and per record files are read
+    /// without prompting for the secret. returns None if the file uses the
+    /// blob layout as it must be decrypted first
+    pub fn read_metadata<P>(path: P) -> Result<Option<RecordMetaDict>>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        if !matches!(TotpFileType::from_path(&path)?, TotpFileType::TOTP) {
+            let totp_file = Self::from_path_with(path, |_| {
+                Err(Error::new(ErrorKind::ChaChaError).with_message("file is not encrypted"))
+            })?;
+
+            return Ok(Some(
+                totp_file
+                    .records
+                    .iter()
+                    .map(|(name, record)| (name.clone(), record.into()))
+                    .collect(),
+            ));
         }
+
+        let data = std::fs::read(&path)?;
+
+        let Some(body) = data.strip_prefix(PER_RECORD_HEADER) else {
+            return Ok(None);
+        };
+
+        let contents: PerRecordContents = serde_json::from_slice(body)?;
+        let mut rtn = IndexMap::with_capacity(contents.records.len());
+
+        for (name, sealed) in contents.records {
+            rtn.insert(name, sealed.meta()?);
+        }
+
+        Ok(Some(rtn))
+    }
+
+    /// the readable information of every record in the file
+    pub fn metadata(&self) -> RecordMetaDict {
+        self.records
+            .iter()
+            .map(|(name, record)| (name.clone(), record.into()))
+            .collect()
     }
 
     /// takes the records of the file and discards the rest
@@ -199,29 +877,281 @@ impl TotpFile {
         self.records
     }
 
-    /// updates the file with the information stored
+    /// serializes the records into the contents of the file
     ///
-    /// if the file was decrypted then it will attempt to encrypt the new data
-    /// in the previous file
-    pub fn update_file(&self) -> Result<()> {
+    /// encrypted files will use the stored key
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
         match self.file_type {
-            TotpFileType::YAML => {
-                serde_yml::to_writer(Self::get_writer(&self.path)?, &self.records)?;
-            }
-            TotpFileType::JSON => {
-                serde_json::to_writer(Self::get_writer(&self.path)?, &self.records)?;
-            }
+            TotpFileType::YAML => Ok(serde_yml::to_string(&self.records)?.into_bytes()),
+            TotpFileType::JSON => Ok(serde_json::to_vec(&self.records)?),
             TotpFileType::TOTP => {
                 let Some(key) = self.key.as_ref() else {
                     return Err(Error::new(ErrorKind::ChaChaError).with_message("missing key"));
                 };
 
-                let contents = Self::encrypt(key, &self.records)?;
-
-                std::fs::write(&self.path, contents)?;
+                match self.layout {
+                    TotpLayout::Blob => Self::encrypt(key, &self.records),
+                    TotpLayout::PerRecord => Self::encrypt_per_record(key, &self.records),
+                }
             }
+        }
+    }
+
+    /// updates the file in the given storage with the information stored
+    pub fn update_storage<S>(&self, storage: &S) -> Result<()>
+    where
+        S: Storage + ?Sized,
+    {
+        let contents = Zeroizing::new(self.to_bytes()?);
+
+        storage.write(&self.path, &contents)
+    }
+
+    /// updates the file with the information stored
+    ///
+    /// if the file was decrypted then it will attempt to encrypt the new data
+    /// in the previous file
+    pub fn update_file(&self) -> Result<()> {
+        self.update_storage(&System)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(issuer: &str) -> TotpRecord {
+        let mut record = TotpRecord::new(
+            SecretBytes::new(b"12345678901234567890".to_vec()),
+            otp::Algo::SHA1,
+            Digits::new(6).unwrap(),
+            NonZeroU64::new(30).unwrap(),
+        )
+        .unwrap();
+
+        record.issuer = Some(issuer.to_owned());
+        record
+    }
+
+    fn sealed_contents(key: &chacha::Key) -> serde_json::Value {
+        let mut records = TotpRecordDict::new();
+        records.insert(String::from("a"), record("Alpha"));
+        records.insert(String::from("b"), record("Beta"));
+
+        let data = TotpFile::encrypt_per_record(key, &records).unwrap();
+        let body = data.strip_prefix(PER_RECORD_HEADER).unwrap();
+
+        serde_json::from_slice(body).unwrap()
+    }
+
+    #[test]
+    fn pin_families() {
+        let mut motp = record("mOTP");
+        motp.family = otp::Family::Motp;
+        motp.step = NonZeroU64::new(otp::MOTP_STEP).unwrap();
+
+        assert!(motp.code_at(1165324428).is_err());
+
+        motp.pin = Some(SecretString::new(String::from("1234")));
+
+        assert_eq!(
+            motp.code_at(1165324428).unwrap(),
+            otp::motp_code(&motp.secret, "1234", motp.digits, 116532442)
+        );
+
+        let mut yandex = record("Yandex");
+        yandex.family = otp::Family::Yandex;
+        yandex.digits = Digits::new(otp::YANDEX_DIGITS).unwrap();
+        yandex.pin = Some(SecretString::new(String::from("7586")));
+
+        assert_eq!(
+            yandex.code_at(1581064020).unwrap(),
+            otp::yandex_code(&yandex.secret, "7586", yandex.digits, 1581064020 / 30).unwrap()
+        );
+    }
+
+    #[test]
+    fn record_offset() {
+        let mut offset = record("Offset");
+        offset.time_offset = 30;
+
+        assert_eq!(offset.adjusted_time(59), 89);
+        assert_eq!(
+            offset.code_at(59).unwrap(),
+            record("Offset").code_at(89).unwrap()
+        );
+        assert_eq!(offset.counter_at(59), (2, 1));
+    }
+
+    #[test]
+    fn blob_short_data() {
+        let key = chacha::make_key("secret").unwrap();
+
+        for len in [
+            0,
+            5,
+            chacha::NONCE_LEN,
+            chacha::NONCE_LEN + chacha::TAG_LEN - 1,
+        ] {
+            assert!(TotpFile::decrypt(&key, &vec![0u8; len]).is_err());
+        }
+    }
+
+    #[test]
+    fn from_bytes_garbage() {
+        for data in [&b""[..], b"abcde", b"TOTP-PER-RECORD\n{", &[0xffu8; 64]] {
+            let result = TotpFile::from_bytes_with("file.totp", data, |_| chacha::make_key("a"));
+
+            assert!(result.is_err());
+        }
+
+        assert!(TotpFile::from_bytes_with("file.json", b"{\"a\":", |_| unreachable!()).is_err());
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let key = chacha::make_key("secret").unwrap();
+        let mut records = TotpRecordDict::new();
+        records.insert(String::from("a"), record("Alpha"));
+
+        let mut data = TotpFile::encrypt(&key, &records).unwrap();
+        let decrypted = TotpFile::decrypt(&key, &data).unwrap();
+
+        assert_eq!(decrypted["a"].issuer.as_deref(), Some("Alpha"));
+
+        let last = data.len() - 1;
+        data[last] ^= 1;
+
+        assert!(TotpFile::decrypt(&key, &data).is_err());
+    }
+
+    #[test]
+    fn per_record_round_trip() {
+        let key = chacha::make_key("secret").unwrap();
+        let contents = sealed_contents(&key);
+        let body = serde_json::to_vec(&contents).unwrap();
+
+        let records = TotpFile::decrypt_per_record(&key, &body).unwrap();
+
+        assert_eq!(
+            records.keys().collect::<Vec<_>>(),
+            vec![&String::from("a"), &String::from("b")]
+        );
+        assert_eq!(records["b"].issuer.as_deref(), Some("Beta"));
+        assert_eq!(&*records["a"].secret, b"12345678901234567890");
+    }
+
+    #[test]
+    fn per_record_wrong_key() {
+        let key = chacha::make_key("secret").unwrap();
+        let body = serde_json::to_vec(&sealed_contents(&key)).unwrap();
+        let wrong = chacha::make_key("wrong").unwrap();
+
+        assert!(TotpFile::decrypt_per_record(&wrong, &body).is_err());
+    }
+
+    #[test]
+    fn per_record_swapped_records() {
+        let key = chacha::make_key("secret").unwrap();
+        let mut contents = sealed_contents(&key);
+        let records = &mut contents["records"];
+
+        let a = records["a"]["record"].take();
+        records["a"]["record"] = records["b"]["record"].take();
+        records["b"]["record"] = a;
+
+        let body = serde_json::to_vec(&contents).unwrap();
+
+        assert!(TotpFile::decrypt_per_record(&key, &body).is_err());
+    }
+
+    #[test]
+    fn per_record_modified_meta() {
+        let key = chacha::make_key("secret").unwrap();
+
+        for (field, value) in [
+            ("issuer", serde_json::json!("Mallory")),
+            ("tags", serde_json::json!(["work"])),
+            ("pinned", serde_json::json!(true)),
+        ] {
+            let mut contents = sealed_contents(&key);
+            let stored = &mut contents["records"]["a"]["meta"];
+            let mut meta: serde_json::Value =
+                serde_json::from_str(stored.as_str().unwrap()).unwrap();
+
+            meta[field] = value;
+            *stored = serde_json::Value::String(meta.to_string());
+
+            let body = serde_json::to_vec(&contents).unwrap();
+
+            assert!(
+                TotpFile::decrypt_per_record(&key, &body).is_err(),
+                "{} was changed without failing",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn per_record_renamed() {
+        let key = chacha::make_key("secret").unwrap();
+        let contents = sealed_contents(&key);
+        let text = serde_json::to_string(&contents)
+            .unwrap()
+            .replace("\"a\":", "\"c\":");
+
+        assert!(TotpFile::decrypt_per_record(&key, text.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn per_record_unknown_meta() {
+        let key = chacha::make_key("secret").unwrap();
+        let data = serde_json::to_vec(&record("Alpha")).unwrap();
+        let meta = String::from("{\"issuer\":\"Alpha\",\"color\":\"red\"}");
+
+        let mut records = IndexMap::new();
+        records.insert(
+            String::from("a"),
+            SealedRecord {
+                record: Sealed::seal(&key, &data, &SealedRecord::aad("a", &meta).unwrap()).unwrap(),
+                meta,
+            },
+        );
+
+        let contents = PerRecordContents {
+            check: Sealed::seal(&key, &[], &PerRecordContents::check_aad(&records).unwrap())
+                .unwrap(),
+            records,
         };
+        let body = serde_json::to_vec(&contents).unwrap();
 
-        Ok(())
+        let records = TotpFile::decrypt_per_record(&key, &body).unwrap();
+
+        assert_eq!(records["a"].issuer.as_deref(), Some("Alpha"));
+    }
+
+    #[test]
+    fn per_record_removed() {
+        let key = chacha::make_key("secret").unwrap();
+        let mut contents = sealed_contents(&key);
+
+        contents["records"].as_object_mut().unwrap().remove("b");
+
+        let body = serde_json::to_vec(&contents).unwrap();
+
+        assert!(TotpFile::decrypt_per_record(&key, &body).is_err());
+    }
+
+    #[test]
+    fn per_record_rolled_back() {
+        let key = chacha::make_key("secret").unwrap();
+        let older = sealed_contents(&key);
+        let mut contents = sealed_contents(&key);
+
+        contents["records"]["a"] = older["records"]["a"].clone();
+
+        let body = serde_json::to_vec(&contents).unwrap();
+
+        assert!(TotpFile::decrypt_per_record(&key, &body).is_err());
     }
 }
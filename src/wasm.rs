@@ -0,0 +1,62 @@
+use wasm_bindgen::prelude::*;
+
+use crate::chacha;
+use crate::error::Error;
+use crate::types::{TotpFile, TotpRecordDict};
+
+/// converts an error into one that can be thrown in javascript
+fn js_error(err: Error) -> JsError {
+    match err.message {
+        Some(msg) => JsError::new(&format!("{}: {}", err.kind, msg)),
+        None => JsError::new(&err.kind.to_string()),
+    }
+}
+
+/// the records of a file opened in the browser
+#[wasm_bindgen]
+pub struct Vault {
+    records: TotpRecordDict,
+}
+
+#[wasm_bindgen]
+impl Vault {
+    /// opens a file from its contents
+    ///
+    /// the type of file is determined by the extension of the name. the
+    /// passphrase is only used if the file is encrypted
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(name: &str, data: &[u8], passphrase: &str) -> Result<Vault, JsError> {
+        let file = TotpFile::from_bytes_with(name, data, |_| chacha::make_key(passphrase))
+            .map_err(js_error)?;
+
+        Ok(Vault {
+            records: file.take_records(),
+        })
+    }
+
+    /// the names of the records in sorted order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.records.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// generates the code of a record for the given unix epoch in seconds
+    pub fn code(&self, name: &str, time: f64) -> Result<String, JsError> {
+        let Some(record) = self.records.get(name) else {
+            return Err(JsError::new(&format!("record \"{}\" does not exist", name)));
+        };
+
+        record.code_at(time as u64).map_err(js_error)
+    }
+
+    /// the number of seconds until the code of a record changes
+    #[wasm_bindgen(js_name = secondsLeft)]
+    pub fn seconds_left(&self, name: &str, time: f64) -> Result<f64, JsError> {
+        let Some(record) = self.records.get(name) else {
+            return Err(JsError::new(&format!("record \"{}\" does not exist", name)));
+        };
+
+        Ok(record.counter_at(time as u64).1 as f64)
+    }
+}